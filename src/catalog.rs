@@ -0,0 +1,402 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fs, io,
+    path::Path,
+};
+
+use glob::glob;
+use log::warn;
+use serde::{de::DeserializeOwned, Deserialize};
+
+use crate::util::parse_duration;
+
+// The maximum difference, in seconds, between durations for songs to be considered the same.
+const DURATION_TOLERANCE: i32 = 2;
+
+/// Existing albums and songs of an artist in an output directory.
+#[derive(Debug, Default)]
+pub struct Catalog {
+    albums: Vec<AlbumEntry>,
+    songs: Vec<Entry>,
+}
+
+#[derive(Debug, Eq, PartialEq)]
+pub struct AlbumEntry {
+    pub id: String,
+    pub original_name: Option<String>,
+    pub urls: Vec<String>,
+}
+
+#[derive(Debug, Eq, PartialEq)]
+pub struct Entry {
+    pub id: String,
+    pub original_name: Option<String>,
+    pub duration: Option<i32>,
+}
+
+impl Catalog {
+    /// Reads the albums (`albums/<artist_id>/*.toml`) and songs (`songs/<artist_id>/*.toml`) of
+    /// an artist.
+    ///
+    /// Durations are taken from the artist's tracklists. Files that cannot be parsed are skipped.
+    pub fn load<P>(prefix: P, artist_id: &str) -> io::Result<Catalog>
+    where
+        P: AsRef<Path>,
+    {
+        let prefix = prefix.as_ref();
+
+        let albums = read_albums(prefix, artist_id)?;
+        let songs = read_songs(prefix, artist_id)?;
+
+        Ok(Catalog { albums, songs })
+    }
+
+    pub fn albums(&self) -> &[AlbumEntry] {
+        &self.albums
+    }
+
+    /// Finds an existing album by one of its release URLs or by its normalized original name.
+    pub fn find_album(&self, url: &str, original_name: &str) -> Option<&AlbumEntry> {
+        if let Some(entry) = self.albums.iter().find(|a| a.urls.iter().any(|u| u == url)) {
+            return Some(entry);
+        }
+
+        let original_name = normalize(original_name);
+
+        self.albums.iter().find(|a| {
+            a.original_name
+                .as_ref()
+                .map(|name| normalize(name) == original_name)
+                .unwrap_or(false)
+        })
+    }
+
+    pub fn songs(&self) -> &[Entry] {
+        &self.songs
+    }
+
+    pub fn song_ids(&self) -> HashSet<String> {
+        self.songs.iter().map(|s| s.id.clone()).collect()
+    }
+
+    /// Finds an existing song by its normalized original name, regardless of its duration.
+    pub fn find_song_by_name(&self, original_name: &str) -> Option<&Entry> {
+        let original_name = normalize(original_name);
+
+        self.songs.iter().find(|s| {
+            s.original_name
+                .as_ref()
+                .map(|name| normalize(name) == original_name)
+                .unwrap_or(false)
+        })
+    }
+
+    /// Finds an existing song by ID or by its normalized original name and duration.
+    pub fn find_song(
+        &self,
+        id: &str,
+        original_name: Option<&str>,
+        duration: i32,
+    ) -> Option<&Entry> {
+        if let Some(entry) = self.songs.iter().find(|s| s.id == id) {
+            return Some(entry);
+        }
+
+        let original_name = normalize(original_name?);
+
+        self.songs.iter().find(|s| {
+            let is_same_name = s
+                .original_name
+                .as_ref()
+                .map(|name| normalize(name) == original_name)
+                .unwrap_or(false);
+
+            let is_same_duration = s
+                .duration
+                .map(|d| (d - duration).abs() <= DURATION_TOLERANCE)
+                .unwrap_or(true);
+
+            is_same_name && is_same_duration
+        })
+    }
+}
+
+/// Removes whitespace and punctuation and lowercases the given name.
+pub fn normalize(s: &str) -> String {
+    s.chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+#[derive(Deserialize)]
+struct RawAlbum {
+    names: Vec<RawName>,
+    #[serde(default)]
+    releases: Vec<RawRelease>,
+}
+
+#[derive(Deserialize)]
+struct RawRelease {
+    #[serde(default)]
+    urls: Vec<RawUrl>,
+}
+
+#[derive(Deserialize)]
+struct RawUrl {
+    url: String,
+}
+
+#[derive(Deserialize)]
+struct RawSong {
+    names: Vec<RawName>,
+}
+
+#[derive(Deserialize)]
+struct RawName {
+    name: String,
+    #[serde(default)]
+    original: bool,
+}
+
+#[derive(Deserialize)]
+struct RawTracklist {
+    #[serde(default)]
+    tracks: Vec<RawTrack>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "kebab-case")]
+struct RawTrack {
+    song_id: String,
+    duration: String,
+}
+
+fn read_albums(prefix: &Path, artist_id: &str) -> io::Result<Vec<AlbumEntry>> {
+    let src_prefix = prefix.join("albums").join(artist_id);
+
+    if !src_prefix.is_dir() {
+        return Ok(Vec::new());
+    }
+
+    let mut albums = Vec::new();
+
+    for entry in fs::read_dir(src_prefix)? {
+        let path = entry?.path();
+
+        if !path.extension().map(|ext| ext == "toml").unwrap_or(false) {
+            continue;
+        }
+
+        let id = match path.file_stem().and_then(|s| s.to_str()) {
+            Some(stem) => String::from(stem),
+            None => continue,
+        };
+
+        let album: RawAlbum = match read_toml(&path) {
+            Ok(album) => album,
+            Err(e) => {
+                warn!("skipping album '{}' ({})", path.display(), e);
+                continue;
+            }
+        };
+
+        let original_name = album.names.into_iter().find(|n| n.original).map(|n| n.name);
+
+        let urls = album
+            .releases
+            .into_iter()
+            .flat_map(|r| r.urls)
+            .map(|u| u.url)
+            .collect();
+
+        albums.push(AlbumEntry {
+            id,
+            original_name,
+            urls,
+        });
+    }
+
+    albums.sort_by(|a, b| a.id.cmp(&b.id));
+
+    Ok(albums)
+}
+
+fn read_songs(prefix: &Path, artist_id: &str) -> io::Result<Vec<Entry>> {
+    let src_prefix = prefix.join("songs").join(artist_id);
+
+    if !src_prefix.is_dir() {
+        return Ok(Vec::new());
+    }
+
+    let durations = read_durations(prefix, artist_id);
+    let mut songs = Vec::new();
+
+    for entry in fs::read_dir(src_prefix)? {
+        let path = entry?.path();
+
+        if !path.extension().map(|ext| ext == "toml").unwrap_or(false) {
+            continue;
+        }
+
+        let id = match path.file_stem().and_then(|s| s.to_str()) {
+            Some(stem) => String::from(stem),
+            None => continue,
+        };
+
+        let original_name = match read_toml::<RawSong>(&path) {
+            Ok(song) => song.names.into_iter().find(|n| n.original).map(|n| n.name),
+            Err(e) => {
+                warn!("skipping song '{}' ({})", path.display(), e);
+                continue;
+            }
+        };
+
+        let duration = durations.get(&format!("{}/{}", artist_id, id)).copied();
+
+        songs.push(Entry {
+            id,
+            original_name,
+            duration,
+        });
+    }
+
+    songs.sort_by(|a, b| a.id.cmp(&b.id));
+
+    Ok(songs)
+}
+
+fn read_toml<T>(path: &Path) -> io::Result<T>
+where
+    T: DeserializeOwned,
+{
+    let data = fs::read_to_string(path)?;
+    toml::from_str(&data).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+fn read_durations(prefix: &Path, artist_id: &str) -> HashMap<String, i32> {
+    let mut durations = HashMap::new();
+
+    let pattern = prefix.join("tracklists").join(artist_id).join("**/*.toml");
+
+    let pattern = match pattern.to_str() {
+        Some(p) => p,
+        None => return durations,
+    };
+
+    let entries = glob(pattern)
+        .expect("invalid glob pattern")
+        .filter_map(Result::ok);
+
+    for entry in entries {
+        let tracklist: RawTracklist = match fs::read_to_string(&entry)
+            .ok()
+            .and_then(|data| toml::from_str(&data).ok())
+        {
+            Some(tracklist) => tracklist,
+            None => {
+                warn!("skipping tracklist '{}'", entry.display());
+                continue;
+            }
+        };
+
+        for track in tracklist.tracks {
+            if let Some(duration) = parse_duration(&track.duration) {
+                durations.insert(track.song_id, duration);
+            }
+        }
+    }
+
+    durations
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_load() {
+        let catalog = Catalog::load("tests/fixtures/fs", "bol4").unwrap();
+
+        assert_eq!(
+            catalog.songs(),
+            &[Entry {
+                id: String::from("uju-reul-jul-ge"),
+                original_name: Some(String::from("우주를 줄게")),
+                duration: Some(274),
+            }]
+        );
+
+        let catalog = Catalog::load("tests/fixtures/fs", "i").unwrap();
+        assert!(catalog.songs().is_empty());
+    }
+
+    #[test]
+    fn test_load_albums() {
+        let catalog = Catalog::load("tests/fixtures/fs", "bol4").unwrap();
+
+        assert_eq!(
+            catalog.albums(),
+            &[AlbumEntry {
+                id: String::from("red-planet"),
+                original_name: Some(String::from("Red Planet")),
+                urls: vec![String::from(
+                    "https://www.melon.com/album/detail.htm?albumId=2668659"
+                )],
+            }]
+        );
+
+        let catalog = Catalog::load("tests/fixtures/fs", "i").unwrap();
+        assert!(catalog.albums().is_empty());
+    }
+
+    #[test]
+    fn test_find_album() {
+        let catalog = Catalog::load("tests/fixtures/fs", "bol4").unwrap();
+
+        assert!(catalog
+            .find_album(
+                "https://www.melon.com/album/detail.htm?albumId=2668659",
+                "Red Diary"
+            )
+            .is_some());
+        assert!(catalog
+            .find_album("https://mora.jp/package/1/2/", "RED PLANET")
+            .is_some());
+        assert!(catalog
+            .find_album("https://mora.jp/package/1/2/", "Red Diary")
+            .is_none());
+    }
+
+    #[test]
+    fn test_find_song() {
+        let catalog = Catalog::load("tests/fixtures/fs", "bol4").unwrap();
+
+        assert!(catalog.find_song("uju-reul-jul-ge", None, 0).is_some());
+        assert!(catalog
+            .find_song("galaxy", Some("우주를  줄게"), 275)
+            .is_some());
+        assert!(catalog
+            .find_song("galaxy", Some("우주를 줄게"), 200)
+            .is_none());
+        assert!(catalog
+            .find_song("galaxy", Some("우주를 줄래"), 274)
+            .is_none());
+        assert!(catalog.find_song("galaxy", None, 274).is_none());
+    }
+
+    #[test]
+    fn test_find_song_by_name() {
+        let catalog = Catalog::load("tests/fixtures/fs", "bol4").unwrap();
+
+        assert!(catalog.find_song_by_name("우주를 줄게").is_some());
+        assert!(catalog.find_song_by_name("우주를줄게").is_some());
+        assert!(catalog.find_song_by_name("Galaxy").is_none());
+    }
+
+    #[test]
+    fn test_normalize() {
+        assert_eq!(normalize("우주를 줄게"), "우주를줄게");
+        assert_eq!(normalize("Girl's Talk (이브, 츄)"), "girlstalk이브츄");
+    }
+}
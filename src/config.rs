@@ -0,0 +1,203 @@
+use std::{collections::HashMap, fs, io, path::Path, str::FromStr, time::Duration};
+
+use serde::Deserialize;
+
+use crate::util::http::{Limit, Limits};
+
+// The longest interval, in seconds, between requests to a host.
+const MAX_INTERVAL: f64 = 3600.0;
+
+/// User configuration, read from a TOML file.
+///
+/// ```toml
+/// [rate-limit]
+/// interval = 1.0
+/// max-concurrent-requests = 2
+///
+/// [rate-limit.hosts."www.melon.com"]
+/// interval = 2.0
+/// ```
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct Config {
+    #[serde(default)]
+    rate_limit: RateLimit,
+}
+
+impl Config {
+    pub fn load<P>(src: P) -> io::Result<Config>
+    where
+        P: AsRef<Path>,
+    {
+        let data = fs::read_to_string(src)?;
+        data.parse()
+    }
+
+    /// Returns the request limits per host.
+    ///
+    /// Host sections override the top-level values, which override the defaults.
+    pub fn limits(&self) -> Limits {
+        let default = self.rate_limit.limit.apply(Limit::default());
+
+        let hosts = self
+            .rate_limit
+            .hosts
+            .iter()
+            .map(|(host, limit)| (host.clone(), limit.apply(default)))
+            .collect();
+
+        Limits { default, hosts }
+    }
+}
+
+impl FromStr for Config {
+    type Err = io::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let config: Config =
+            toml::from_str(s).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+
+        config.rate_limit.validate()?;
+
+        Ok(config)
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct RateLimit {
+    #[serde(flatten)]
+    limit: RawLimit,
+    #[serde(default)]
+    hosts: HashMap<String, RawLimit>,
+}
+
+impl RateLimit {
+    fn validate(&self) -> io::Result<()> {
+        self.limit.validate("rate-limit")?;
+
+        for (host, limit) in &self.hosts {
+            limit.validate(&format!("rate-limit.hosts.\"{}\"", host))?;
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+struct RawLimit {
+    // seconds
+    interval: Option<f64>,
+    max_concurrent_requests: Option<usize>,
+}
+
+impl RawLimit {
+    // Checks that the values are usable, since TOML allows, e.g., `inf` and `nan`.
+    fn validate(&self, section: &str) -> io::Result<()> {
+        if let Some(secs) = self.interval {
+            if !secs.is_finite() || !(0.0..=MAX_INTERVAL).contains(&secs) {
+                return Err(invalid_data(format!(
+                    "{}: invalid interval: {} (expected 0 to {} seconds)",
+                    section, secs, MAX_INTERVAL
+                )));
+            }
+        }
+
+        if self.max_concurrent_requests == Some(0) {
+            return Err(invalid_data(format!(
+                "{}: invalid max-concurrent-requests: 0 (expected 1 or more)",
+                section
+            )));
+        }
+
+        Ok(())
+    }
+
+    fn apply(&self, limit: Limit) -> Limit {
+        Limit {
+            interval: self
+                .interval
+                .map(Duration::from_secs_f64)
+                .unwrap_or(limit.interval),
+            max_concurrent_requests: self
+                .max_concurrent_requests
+                .unwrap_or(limit.max_concurrent_requests),
+        }
+    }
+}
+
+fn invalid_data(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_limits() {
+        let config: Config = r#"
+            [rate-limit]
+            interval = 0.5
+
+            [rate-limit.hosts."www.melon.com"]
+            interval = 2.0
+            max-concurrent-requests = 1
+        "#
+        .parse()
+        .unwrap();
+
+        let limits = config.limits();
+
+        assert_eq!(
+            limits.default,
+            Limit {
+                interval: Duration::from_millis(500),
+                max_concurrent_requests: 2,
+            }
+        );
+
+        assert_eq!(
+            limits.get("www.melon.com"),
+            Limit {
+                interval: Duration::from_secs(2),
+                max_concurrent_requests: 1,
+            }
+        );
+
+        assert_eq!(Config::default().limits(), Limits::default());
+    }
+
+    #[test]
+    fn test_from_str_with_invalid_value() {
+        assert!("[rate-limit]\ninterval = \"fast\"\n"
+            .parse::<Config>()
+            .is_err());
+    }
+
+    #[test]
+    fn test_from_str_with_invalid_interval() {
+        for interval in &["inf", "-inf", "nan", "1e300", "-1.0"] {
+            let data = format!(
+                "[rate-limit.hosts.\"www.melon.com\"]\ninterval = {}\n",
+                interval
+            );
+
+            let e = data.parse::<Config>().unwrap_err();
+            assert_eq!(e.kind(), io::ErrorKind::InvalidData);
+            assert!(e.to_string().contains("www.melon.com"), "{}", e);
+        }
+
+        let e = "[rate-limit]\ninterval = inf\n"
+            .parse::<Config>()
+            .unwrap_err();
+        assert!(e.to_string().starts_with("rate-limit: invalid interval"));
+    }
+
+    #[test]
+    fn test_from_str_with_no_concurrent_requests() {
+        assert!("[rate-limit]\nmax-concurrent-requests = 0\n"
+            .parse::<Config>()
+            .is_err());
+    }
+}
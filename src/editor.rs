@@ -1,13 +1,40 @@
-use rustyline::{self, error::ReadlineError};
+mod bulk;
+mod document;
+mod edits;
+mod prompt;
+mod screen;
+mod session;
+
+pub use self::{
+    edits::{Edit, Edits, NameEdit, SongEdit},
+    prompt::{set_artist_ids, set_history_file},
+};
+
+use std::{collections::HashSet, error, fmt, io, mem, path::Path};
+
+use chrono::NaiveDate;
+use log::warn;
+use rustyline::error::ReadlineError;
+use serde::{Deserialize, Serialize};
+use url::Url;
 
 use crate::{
-    models::{album, Album, Name, Song},
+    catalog::{self, Catalog},
+    extractors::{Candidate, Warning},
+    models::{album, Album, Lyrics, Name, Song},
     util::{
         format_duration,
-        inflector::{parameterize, titleize},
+        inflector::{parameterize, parse_title, titleize, Version},
+        parse_duration,
     },
 };
 
+use self::{
+    prompt::Completion,
+    session::{Autosave, Session},
+};
+
+#[derive(Clone, Deserialize, PartialEq, Serialize)]
 pub struct AlbumInput {
     pub id: String,
 
@@ -38,7 +65,7 @@ impl<'a> From<&'a Album> for AlbumInput {
     }
 }
 
-#[derive(Default)]
+#[derive(Clone, Default, Deserialize, PartialEq, Serialize)]
 pub struct NameInput {
     pub name: String,
     pub locale: String,
@@ -61,6 +88,7 @@ impl<'a> From<&'a Name> for NameInput {
     }
 }
 
+#[derive(Clone, Deserialize, PartialEq, Serialize)]
 pub struct SongInput {
     pub id: String,
 
@@ -68,6 +96,15 @@ pub struct SongInput {
     pub duration: i32,
 
     pub names: Vec<NameInput>,
+
+    pub featured_artists: Vec<String>,
+    pub performers: Vec<String>,
+    pub versions: Vec<Version>,
+
+    pub base_song_id: Option<String>,
+    pub is_existing: bool,
+
+    pub lyrics: Option<Lyrics>,
 }
 
 impl<'a> From<&'a Song> for SongInput {
@@ -79,15 +116,46 @@ impl<'a> From<&'a Song> for SongInput {
             duration: song.duration,
 
             names: song.names.iter().map(NameInput::from).collect(),
+
+            featured_artists: song.featured_artists.clone(),
+            performers: song.performers.clone(),
+            versions: song.versions.clone(),
+
+            base_song_id: song.base_song_id.clone(),
+            is_existing: song.is_existing,
+
+            lyrics: song.lyrics.clone(),
         }
     }
 }
 
-pub fn edit(album: &Album) -> Album {
-    let mut form = prepare(album);
+/// Prompts to edit an album and its songs.
+///
+/// Each prompt accepts `z` to undo and `Z` to redo the last change. The form is saved to
+/// `autosave_dir` after every change, and an unfinished edit of the same album, e.g., one that was
+/// interrupted, can be resumed.
+pub fn edit<P>(album: &Album, catalog: &Catalog, warnings: &[Warning], autosave_dir: P) -> Album
+where
+    P: AsRef<Path>,
+{
+    let autosave = Autosave::new(autosave_dir, &album.url);
+
+    let form = match autosave.load() {
+        Ok(Some(form)) if confirm_resume() => form,
+        Ok(_) => prepare(album),
+        Err(e) => {
+            warn!("failed to read autosave: {}", e);
+            prepare(album)
+        }
+    };
+
+    let mut session = Session::new(form, Some(autosave));
+
+    prompt::set_song_ids(catalog.song_ids());
 
     loop {
-        edit_album(&mut form);
+        edit_album(&mut session, catalog);
+        print_warnings(warnings);
 
         if let Ok(input) = readline("> Commit? [Y/n] ") {
             if input.is_empty() || input == "y" {
@@ -96,7 +164,284 @@ pub fn edit(album: &Album) -> Album {
         }
     }
 
-    commit(form)
+    commit(session.finish())
+}
+
+fn confirm_resume() -> bool {
+    match readline("> Resume the unfinished edit of this album? [Y/n] ") {
+        Ok(input) => input.is_empty() || input == "y",
+        Err(_) => false,
+    }
+}
+
+/// The album or one of its songs, by index.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum Target {
+    Album,
+    Song(usize),
+}
+
+impl Target {
+    fn names(self, form: &AlbumInput) -> Option<(&String, &Vec<NameInput>)> {
+        match self {
+            Target::Album => Some((&form.id, &form.names)),
+            Target::Song(i) => form.songs.get(i).map(|s| (&s.id, &s.names)),
+        }
+    }
+
+    fn names_mut(self, form: &mut AlbumInput) -> Option<(&mut String, &mut Vec<NameInput>)> {
+        match self {
+            Target::Album => Some((&mut form.id, &mut form.names)),
+            Target::Song(i) => form.songs.get_mut(i).map(|s| (&mut s.id, &mut s.names)),
+        }
+    }
+}
+
+/// An edit that cannot be decided without prompts.
+#[derive(Debug, Eq, PartialEq)]
+pub enum EditError {
+    /// A list of names has no default name to generate an ID from.
+    MissingDefaultName(String),
+    /// The default name generates an empty ID, e.g., a name with only symbols.
+    EmptyId(String),
+    /// Two songs in the album have the same ID.
+    DuplicateSongId(String),
+    /// An edit refers to a name index that does not exist.
+    InvalidNameIndex { target: String, index: usize },
+    /// An edit refers to a song position that does not exist.
+    MissingSong(i32),
+}
+
+impl error::Error for EditError {}
+
+impl fmt::Display for EditError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::MissingDefaultName(target) => {
+                write!(f, "{}: missing default name to generate an ID from", target)
+            }
+            Self::EmptyId(target) => write!(f, "{}: default name generates an empty ID", target),
+            Self::DuplicateSongId(id) => write!(f, "duplicate song ID: {}", id),
+            Self::InvalidNameIndex { target, index } => {
+                write!(f, "{}: invalid name index: {}", target, index)
+            }
+            Self::MissingSong(position) => write!(f, "missing song at position {}", position),
+        }
+    }
+}
+
+/// Edits an album as a TOML document in the user's editor, e.g., `$EDITOR`.
+///
+/// The document is reopened with its errors until it is valid. It returns `None` if all lines
+/// are deleted.
+pub fn edit_external(
+    album: &Album,
+    catalog: &Catalog,
+    warnings: &[Warning],
+) -> io::Result<Option<Album>> {
+    let mut form = prepare(album);
+    link_existing_songs(&mut form, catalog);
+
+    let song_ids: Vec<String> = form.songs.iter().map(|s| s.id.clone()).collect();
+    let mut text = document::render(&form, warnings);
+
+    loop {
+        let edited = document::open_editor(&text)?;
+
+        if document::is_empty(&edited) {
+            return Ok(None);
+        }
+
+        match document::apply(&mut form, &edited) {
+            Ok(()) => {
+                relink_changed_songs(&mut form, &song_ids, catalog);
+                link_variants(&mut form, catalog);
+                return Ok(Some(commit(form)));
+            }
+            Err(errors) => text = document::annotate(&edited, &errors),
+        }
+    }
+}
+
+/// Edits an album in a full-screen terminal editor.
+///
+/// The album and its songs are listed in a table, next to a preview of the rendered album. It
+/// returns `None` if the editor is quit without saving.
+pub fn edit_full_screen(
+    album: &Album,
+    catalog: &Catalog,
+    warnings: &[Warning],
+    artist_id: &str,
+) -> io::Result<Option<Album>> {
+    let mut form = prepare(album);
+    link_existing_songs(&mut form, catalog);
+
+    let song_ids: Vec<String> = form.songs.iter().map(|s| s.id.clone()).collect();
+
+    let mut form = match screen::run(form, warnings, artist_id)? {
+        Some(form) => form,
+        None => return Ok(None),
+    };
+
+    relink_changed_songs(&mut form, &song_ids, catalog);
+    link_variants(&mut form, catalog);
+
+    Ok(Some(commit(form)))
+}
+
+/// Applies scripted edits, e.g., from an edit file, to an album.
+pub fn apply_edits(album: &Album, edits: &Edits) -> Result<Album, EditError> {
+    let mut form = prepare(album);
+    edits.apply(&mut form)?;
+    Ok(commit(form))
+}
+
+/// Edits an album without prompts, e.g., for scripts.
+///
+/// If `guess_names` is set, a name is guessed for the album and each song, as with `g` in the
+/// editor, and its ID is generated from the new default name. Songs that match an existing song in
+/// the catalog are linked, which is the default of the editor.
+pub fn auto_edit(album: &Album, catalog: &Catalog, guess_names: bool) -> Result<Album, EditError> {
+    let mut form = prepare(album);
+
+    if guess_names_and_reset_id(&form.id, &mut form.names, guess_names) {
+        form.id = generate_id(&form.names, || String::from("album"))?;
+    }
+
+    for song in &mut form.songs {
+        let position = song.position;
+
+        if guess_names_and_reset_id(&song.id, &mut song.names, guess_names) {
+            song.id = generate_id(&song.names, || format!("track {}", position))?;
+        }
+    }
+
+    link_existing_songs(&mut form, catalog);
+
+    let mut song_ids = HashSet::new();
+
+    for song in &form.songs {
+        if !song_ids.insert(song.id.clone()) {
+            return Err(EditError::DuplicateSongId(song.id.clone()));
+        }
+    }
+
+    link_variants(&mut form, catalog);
+
+    Ok(commit(form))
+}
+
+// Optionally guesses a name and returns whether the ID must be generated again, i.e., it is empty
+// or was generated from a default name that changed. An explicit ID is kept.
+fn guess_names_and_reset_id(id: &str, names: &mut Vec<NameInput>, guess_names: bool) -> bool {
+    let is_default_id = uses_default_id(id, names);
+
+    if guess_names && guess_missing_name(names) && is_default_id {
+        return true;
+    }
+
+    id.is_empty()
+}
+
+// Guesses a name unless one was already added for the same locale.
+fn guess_missing_name(names: &mut Vec<NameInput>) -> bool {
+    if names.iter().any(|n| n.locale == "ko-Latn") {
+        return false;
+    }
+
+    if guess_name(names) {
+        let i = names.len() - 1;
+        update_name_flags(names, i);
+        true
+    } else {
+        false
+    }
+}
+
+fn generate_id<F>(names: &[NameInput], target: F) -> Result<String, EditError>
+where
+    F: Fn() -> String,
+{
+    let name = names
+        .iter()
+        .find(|n| n.is_default && !n.delete)
+        .ok_or_else(|| EditError::MissingDefaultName(target()))?;
+
+    let id = parameterize(&name.name);
+
+    if id.is_empty() {
+        Err(EditError::EmptyId(target()))
+    } else {
+        Ok(id)
+    }
+}
+
+/// Prompts for a local artist ID, completing the IDs set by `set_artist_ids`.
+///
+/// It returns `None` if the prompt is canceled.
+pub fn prompt_artist_id() -> Option<String> {
+    let input =
+        editline_with_completion("> Artist ID [empty to cancel]: ", "", Completion::ArtistId)
+            .ok()?;
+
+    let input = input.trim();
+
+    if input.is_empty() {
+        None
+    } else {
+        Some(input.into())
+    }
+}
+
+/// Lists search candidates and prompts to pick one.
+///
+/// It returns the index of the picked candidate or `None` if the prompt is canceled.
+pub fn pick(candidates: &[Candidate]) -> Option<usize> {
+    for (i, candidate) in candidates.iter().enumerate() {
+        println!("  {}. {}", i + 1, candidate);
+    }
+
+    println!();
+
+    if candidates.is_empty() {
+        return None;
+    }
+
+    let prompt = format!("> Album [1-{}, empty to cancel]: ", candidates.len());
+
+    loop {
+        let input = readline(&prompt).ok()?;
+
+        if input.is_empty() {
+            return None;
+        }
+
+        if let Some(i) = parse_pick(&input, candidates.len()) {
+            return Some(i);
+        }
+    }
+}
+
+// Parses a 1-based candidate number as an index.
+fn parse_pick(s: &str, len: usize) -> Option<usize> {
+    match s.trim().parse::<usize>() {
+        Ok(n) if n >= 1 && n <= len => Some(n - 1),
+        _ => None,
+    }
+}
+
+fn print_warnings(warnings: &[Warning]) {
+    if warnings.is_empty() {
+        return;
+    }
+
+    println!("warnings:");
+
+    for warning in warnings {
+        println!("  - {}", warning);
+    }
+
+    println!();
 }
 
 fn prepare(album: &Album) -> AlbumInput {
@@ -107,27 +452,602 @@ fn commit(input: AlbumInput) -> Album {
     Album::from(input)
 }
 
-fn edit_album(album: &mut AlbumInput) {
-    println!("kind: {}", album.kind);
-    println!("released on: {}", album.released_on);
+fn edit_album(session: &mut Session, catalog: &Catalog) {
+    edit_release(session);
+    edit_all_names(session);
+    edit_names(session, Target::Album);
+    edit_tracklist(session);
+    edit_songs(session, catalog);
+}
+
+// Handles the undo (`z`) and redo (`Z`) commands. It returns whether the input is one of them.
+fn handle_history(session: &mut Session, input: &str) -> bool {
+    let (is_changed, action) = match input {
+        "z" => (session.undo(), "undo"),
+        "Z" => (session.redo(), "redo"),
+        _ => return false,
+    };
+
+    if !is_changed {
+        println!("nothing to {}", action);
+    }
+
+    println!();
+
+    true
+}
+
+fn edit_release(session: &mut Session) {
+    loop {
+        let album = &session.form;
+
+        println!("kind: {}", album.kind);
+        println!("released on: {}", album.released_on);
+        println!("country: {}", album.country);
+        println!("url: {}", album.url);
+        println!(
+            "artwork url: {}",
+            album.artwork_url.as_deref().unwrap_or("(none)")
+        );
+        println!();
+
+        if let Ok(input) = readline("> Edit album? [k/r/c/u/w/z/Z/N] ") {
+            if handle_history(session, &input) {
+                continue;
+            }
+
+            let before = session.form.clone();
+            let album = &mut session.form;
+
+            match input.as_ref() {
+                "k" => {
+                    let text = album.kind.to_string();
+                    edit_field("kind", &text, &mut album.kind, |s| s.parse());
+                }
+                "r" => {
+                    let text = album.released_on.clone();
+                    edit_field("released on", &text, &mut album.released_on, parse_date);
+                }
+                "c" => {
+                    let text = album.country.clone();
+                    edit_field("country", &text, &mut album.country, parse_country);
+                }
+                "u" => {
+                    let text = album.url.clone();
+                    edit_field("url", &text, &mut album.url, parse_url);
+                }
+                "w" => {
+                    let text = album.artwork_url.clone().unwrap_or_default();
+                    edit_field(
+                        "artwork url (- to remove)",
+                        &text,
+                        &mut album.artwork_url,
+                        parse_artwork_url,
+                    );
+                }
+                "n" | "" => break,
+                _ => {}
+            }
+
+            session.record(before);
+        }
+
+        println!();
+    }
+}
+
+fn edit_all_names(session: &mut Session) {
+    loop {
+        let input = match readline("> Edit all names? [g/t/l/f/z/Z/N] ") {
+            Ok(input) => input,
+            Err(_) => continue,
+        };
+
+        if handle_history(session, &input) {
+            continue;
+        }
+
+        let operation = match input.as_ref() {
+            "g" => bulk::Operation::GuessNames,
+            "t" => bulk::Operation::TitleizeRomanizations,
+            "l" => match prompt_set_locale() {
+                Some(operation) => operation,
+                None => continue,
+            },
+            "f" => match prompt_replace() {
+                Some(operation) => operation,
+                None => continue,
+            },
+            "n" | "" => break,
+            _ => continue,
+        };
+
+        let mut preview = session.form.clone();
+        let kept_names = bulk::apply(&mut preview, &operation);
+
+        for line in &kept_names {
+            println!("  {}", line);
+        }
+
+        let changes = bulk::diff(&session.form, &preview);
+
+        if changes.is_empty() {
+            println!("no changes");
+            println!();
+            continue;
+        }
+
+        for change in &changes {
+            println!("  {}", change);
+        }
+
+        let prompt = format!("> Apply {} change(s)? [Y/n] ", changes.len());
+
+        if let Ok(input) = readline(&prompt) {
+            if input.is_empty() || input == "y" {
+                let before = mem::replace(&mut session.form, preview);
+                session.record(before);
+            }
+        }
+
+        println!();
+    }
+}
+
+fn prompt_set_locale() -> Option<bulk::Operation> {
+    let script = readline("  script (latin, hangul, kana, han): ").ok()?;
+
+    let script = match script.trim().parse() {
+        Ok(script) => script,
+        Err(e) => {
+            println!("  {}", e);
+            return None;
+        }
+    };
+
+    let locale = editline_with_completion("  locale: ", "", Completion::Locale).ok()?;
+    let locale = locale.trim();
+
+    if locale.is_empty() {
+        return None;
+    }
+
+    Some(bulk::Operation::SetLocale {
+        script,
+        locale: locale.into(),
+    })
+}
+
+fn prompt_replace() -> Option<bulk::Operation> {
+    let from = readline("  find: ").ok()?;
+
+    if from.is_empty() {
+        return None;
+    }
+
+    let to = readline("  replace with: ").ok()?;
+
+    Some(bulk::Operation::Replace { from, to })
+}
+
+// Prompts for a new value, keeping the current value if the input is empty or invalid.
+fn edit_field<T, F>(label: &str, text: &str, value: &mut T, parse: F)
+where
+    F: Fn(&str) -> Result<T, String>,
+{
+    let prompt = format!("  {} [{}]: ", label, text);
+
+    if let Ok(input) = editline(&prompt, text) {
+        let input = input.trim();
+
+        if input.is_empty() {
+            return;
+        }
+
+        match parse(input) {
+            Ok(v) => *value = v,
+            Err(e) => println!("  {}", e),
+        }
+    }
+}
+
+fn edit_tracklist(session: &mut Session) {
+    loop {
+        println!("tracklist:");
+
+        for song in &session.form.songs {
+            println!(
+                "  {}. {} ({})",
+                song.position,
+                song.id,
+                format_duration(song.duration)
+            );
+        }
+
+        println!();
+
+        if let Ok(input) = readline("> Edit tracklist? [d/i/m/t/r/z/Z/N] ") {
+            if handle_history(session, &input) {
+                continue;
+            }
+
+            let before = session.form.clone();
+            let songs = &mut session.form.songs;
+
+            match input.as_ref() {
+                "d" => {
+                    if let Some(position) = prompt_position() {
+                        remove_song(songs, position);
+                    }
+                }
+                "i" => {
+                    if let Some(song) = new_song() {
+                        insert_song(songs, song);
+                    }
+                }
+                "m" => {
+                    if let (Some(from), Some(to)) = (prompt_position(), prompt_position()) {
+                        move_song(songs, from, to);
+                    }
+                }
+                "t" => {
+                    if let Some(song) =
+                        prompt_position().and_then(|p| songs.iter_mut().find(|s| s.position == p))
+                    {
+                        edit_duration(song);
+                    }
+                }
+                "r" => renumber_songs(songs),
+                "n" | "" => break,
+                _ => {}
+            }
+
+            session.record(before);
+        }
+
+        println!();
+    }
+}
+
+// Prompts for the position, duration, and default name of a new song.
+fn new_song() -> Option<SongInput> {
+    let position = prompt_position()?;
+
+    let mut name = NameInput {
+        is_original: true,
+        is_default: true,
+        ..NameInput::default()
+    };
+
+    edit_name(&mut name);
+
+    if name.name.is_empty() || name.locale.is_empty() {
+        return None;
+    }
+
+    let mut song = SongInput {
+        id: parameterize(&name.name),
+
+        position,
+        duration: 0,
+
+        names: vec![name],
+
+        featured_artists: Vec::new(),
+        performers: Vec::new(),
+        versions: Vec::new(),
+
+        base_song_id: None,
+        is_existing: false,
+
+        lyrics: None,
+    };
+
+    edit_duration(&mut song);
+
+    if song.duration == 0 {
+        println!("  missing duration");
+        return None;
+    }
+
+    Some(song)
+}
+
+fn edit_duration(song: &mut SongInput) {
+    let text = format_duration(song.duration);
+
+    edit_field("duration (m:ss)", &text, &mut song.duration, |s| {
+        parse_duration(s)
+            .filter(|&duration| duration > 0)
+            .ok_or_else(|| format!("invalid duration: {} (expected m:ss)", s))
+    });
+}
+
+/// Removes the song at a position. The positions of the other songs are unchanged.
+fn remove_song(songs: &mut Vec<SongInput>, position: i32) -> bool {
+    match songs.iter().position(|s| s.position == position) {
+        Some(i) => {
+            songs.remove(i);
+            true
+        }
+        None => false,
+    }
+}
+
+/// Inserts a song at its position, moving the songs at and after it down by one.
+///
+/// It returns `false` if the position is not positive.
+fn insert_song(songs: &mut Vec<SongInput>, song: SongInput) -> bool {
+    if song.position < 1 {
+        return false;
+    }
+
+    for s in songs.iter_mut().filter(|s| s.position >= song.position) {
+        s.position += 1;
+    }
+
+    let i = songs
+        .iter()
+        .position(|s| s.position > song.position)
+        .unwrap_or(songs.len());
+
+    songs.insert(i, song);
+
+    true
+}
+
+/// Moves the song at a position to another position and renumbers the tracklist.
+fn move_song(songs: &mut Vec<SongInput>, from: i32, to: i32) -> bool {
+    let i = match songs.iter().position(|s| s.position == from) {
+        Some(i) => i,
+        None => return false,
+    };
+
+    let song = songs.remove(i);
+    let j = ((to - 1).max(0) as usize).min(songs.len());
+    songs.insert(j, song);
+
+    renumber_songs(songs);
 
-    edit_names(&mut album.id, &mut album.names);
-    edit_songs(&mut album.songs);
+    true
 }
 
-fn edit_songs(songs: &mut [SongInput]) {
-    for song in songs {
+/// Numbers the songs from 1 in tracklist order, e.g., to close gaps left by removed songs.
+fn renumber_songs(songs: &mut [SongInput]) {
+    for (i, song) in songs.iter_mut().enumerate() {
+        song.position = i as i32 + 1;
+    }
+}
+
+fn edit_songs(session: &mut Session, catalog: &Catalog) {
+    let mut i = 0;
+
+    // Songs are looked up by index, since an undo can change the tracklist.
+    while i < session.form.songs.len() {
+        let song = &session.form.songs[i];
+
         println!("position: {}", song.position);
         println!("duration: {}", format_duration(song.duration));
 
-        edit_names(&mut song.id, &mut song.names);
+        if !song.featured_artists.is_empty() {
+            println!("featuring: {}", song.featured_artists.join(", "));
+        }
+
+        if !song.performers.is_empty() {
+            println!("performers: {}", song.performers.join(", "));
+        }
+
+        if !song.versions.is_empty() {
+            let tags: Vec<&str> = song.versions.iter().map(|v| v.tag.as_str()).collect();
+            println!("versions: {}", tags.join(", "));
+        }
+
+        if let Some(ref lyrics) = song.lyrics {
+            println!(
+                "lyrics: {} line(s) (locale: {})",
+                lyrics.text.lines().count(),
+                lyrics.locale
+            );
+        }
+
+        if let Some(ref base_song_id) = song.base_song_id {
+            println!("base song: {}", base_song_id);
+        }
+
+        if song.is_existing {
+            println!("existing: true");
+        }
+
+        let id = song.id.clone();
+
+        edit_names(session, Target::Song(i));
+
+        let before = session.form.clone();
+
+        if let Some(song) = session.form.songs.get_mut(i) {
+            if song.id != id {
+                relink_song(song, &catalog.song_ids());
+            } else if !song.is_existing {
+                link_existing_song(song, catalog);
+            }
+        }
+
+        if i < session.form.songs.len() {
+            link_base_song(&mut session.form, i, catalog);
+        }
+
+        session.record(before);
+
+        i += 1;
     }
 }
 
-fn edit_names(id: &mut String, names: &mut Vec<NameInput>) {
-    let mut use_default_id = true;
+/// Prompts to link a song to an existing song in the catalog, if one matches.
+///
+/// A linked song takes the ID of the existing song and is not written again.
+fn link_existing_song(song: &mut SongInput, catalog: &Catalog) {
+    let original_name = original_name(&song.names);
+
+    let entry = match catalog.find_song(&song.id, original_name, song.duration) {
+        Some(entry) => entry,
+        None => return,
+    };
+
+    let prompt = format!("> Link to existing song '{}'? [Y/n] ", entry.id);
+
+    if let Ok(input) = readline(&prompt) {
+        let is_confirmed = input.is_empty() || input == "y";
+        let id = entry.id.clone();
+
+        if link_song(song, &id, is_confirmed, &catalog.song_ids()) {
+            println!("  id: {}", song.id);
+        }
+    }
+
+    println!();
+}
+
+// Links a song to an existing song or, if declined, moves it off the ID of the existing song,
+// which would otherwise be overwritten. It returns whether the ID was moved.
+fn link_song(
+    song: &mut SongInput,
+    existing_id: &str,
+    is_confirmed: bool,
+    song_ids: &HashSet<String>,
+) -> bool {
+    if is_confirmed {
+        song.id = existing_id.into();
+        song.is_existing = true;
+        false
+    } else if song.id == existing_id {
+        song.id = unique_song_id(&song.id, song_ids);
+        true
+    } else {
+        false
+    }
+}
+
+// Appends the first free numeric suffix to an ID, e.g., "x-2" when "x" exists.
+fn unique_song_id(id: &str, song_ids: &HashSet<String>) -> String {
+    (2..)
+        .map(|n| format!("{}-{}", id, n))
+        .find(|candidate| !song_ids.contains(candidate))
+        .expect("unbounded suffixes")
+}
+
+// Links songs that match an existing song in the catalog without prompts.
+fn link_existing_songs(form: &mut AlbumInput, catalog: &Catalog) {
+    for song in form.songs.iter_mut().filter(|s| !s.is_existing) {
+        let original_name = original_name(&song.names);
+
+        if let Some(entry) = catalog.find_song(&song.id, original_name, song.duration) {
+            song.id = entry.id.clone();
+            song.is_existing = true;
+        }
+    }
+}
+
+// Checks the links of songs whose IDs changed from the given IDs, e.g., in a document. A song with
+// a changed ID is existing only if its new ID is an existing song.
+fn relink_changed_songs(form: &mut AlbumInput, song_ids: &[String], catalog: &Catalog) {
+    let existing_song_ids = catalog.song_ids();
+
+    for (song, id) in form.songs.iter_mut().zip(song_ids) {
+        if song.id != *id {
+            relink_song(song, &existing_song_ids);
+        }
+    }
+}
+
+// Links a song whose ID changed, e.g., to a completed song ID, if the new ID is an existing song.
+fn relink_song(song: &mut SongInput, existing_song_ids: &HashSet<String>) {
+    song.is_existing = existing_song_ids.contains(&song.id);
+}
+
+/// Prompts to link a variant, e.g., an instrumental, to its base song, if one is found.
+fn link_base_song(form: &mut AlbumInput, i: usize, catalog: &Catalog) {
+    if form.songs[i].base_song_id.is_some() {
+        return;
+    }
+
+    let base_song_id = match find_base_song_id(form, i, catalog) {
+        Some(id) => id,
+        None => return,
+    };
+
+    let prompt = format!("> Link to base song '{}'? [Y/n] ", base_song_id);
+
+    if let Ok(input) = readline(&prompt) {
+        if input.is_empty() || input == "y" {
+            form.songs[i].base_song_id = Some(base_song_id);
+        }
+    }
+
+    println!();
+}
+
+// Links all variants to their base songs without prompts.
+fn link_variants(form: &mut AlbumInput, catalog: &Catalog) {
+    for i in 0..form.songs.len() {
+        if form.songs[i].base_song_id.is_none() {
+            form.songs[i].base_song_id = find_base_song_id(form, i, catalog);
+        }
+    }
+}
+
+/// Finds the ID of the base song of a variant by the base title of the variant's original name,
+/// e.g., "Galaxy" for "Galaxy (Inst.)".
+///
+/// The base song is searched for first in the album and then in the catalog.
+fn find_base_song_id(form: &AlbumInput, i: usize, catalog: &Catalog) -> Option<String> {
+    let song = &form.songs[i];
+
+    if song.versions.is_empty() {
+        return None;
+    }
+
+    let base = parse_title(original_name(&song.names)?).base;
+    let normalized_base = catalog::normalize(&base);
+
+    let base_song_id = form
+        .songs
+        .iter()
+        .enumerate()
+        .find(|&(j, s)| {
+            j != i
+                && original_name(&s.names)
+                    .map(|name| catalog::normalize(name) == normalized_base)
+                    .unwrap_or(false)
+        })
+        .map(|(_, s)| s.id.clone())
+        .or_else(|| catalog.find_song_by_name(&base).map(|e| e.id.clone()))?;
+
+    if base_song_id == song.id {
+        None
+    } else {
+        Some(base_song_id)
+    }
+}
+
+fn original_name(names: &[NameInput]) -> Option<&str> {
+    names
+        .iter()
+        .find(|n| n.is_original && !n.delete)
+        .map(|n| n.name.as_str())
+}
+
+fn edit_names(session: &mut Session, target: Target) {
+    let mut use_default_id = match target.names(&session.form) {
+        Some((id, names)) => uses_default_id(id, names),
+        None => return,
+    };
 
     loop {
+        let (id, names) = match target.names(&session.form) {
+            Some(target) => target,
+            None => return,
+        };
+
         println!("id: {}", id);
         println!("names:");
 
@@ -142,7 +1062,22 @@ fn edit_names(id: &mut String, names: &mut Vec<NameInput>) {
 
         println!();
 
-        if let Ok(input) = readline("> Edit name? [a/e/d/g/i/N] ") {
+        if let Ok(input) = readline("> Edit name? [a/e/d/g/i/z/Z/N] ") {
+            if handle_history(session, &input) {
+                if let Some((id, names)) = target.names(&session.form) {
+                    use_default_id = uses_default_id(id, names);
+                }
+
+                continue;
+            }
+
+            let before = session.form.clone();
+
+            let (id, names) = match target.names_mut(&mut session.form) {
+                Some(target) => target,
+                None => return,
+            };
+
             match input.as_ref() {
                 "a" => {
                     add_name(names);
@@ -171,25 +1106,39 @@ fn edit_names(id: &mut String, names: &mut Vec<NameInput>) {
                     }
                 }
                 "i" => {
-                    edit_id(id);
+                    let completion = match target {
+                        Target::Album => Completion::None,
+                        Target::Song(_) => Completion::SongId,
+                    };
+
+                    edit_id(id, completion);
                     use_default_id = false;
                 }
                 "n" | "" => break,
                 _ => {}
             }
-        }
 
-        if use_default_id {
-            *id = parameterize(&default_name(names).expect("missing default name"));
+            if use_default_id {
+                *id = parameterize(&default_name(names).expect("missing default name"));
+            }
+
+            session.record(before);
         }
 
         println!();
     }
 }
 
-fn edit_id(id: &mut String) {
+// An ID that differs from the default name was set explicitly, e.g., by an edit file.
+fn uses_default_id(id: &str, names: &[NameInput]) -> bool {
+    default_name(names)
+        .map(|name| parameterize(&name) == id)
+        .unwrap_or(true)
+}
+
+fn edit_id(id: &mut String, completion: Completion) {
     let prompt = format!("  id [{}]: ", id);
-    if let Ok(raw_id) = editline(&prompt, id) {
+    if let Ok(raw_id) = editline_with_completion(&prompt, id, completion) {
         if !raw_id.is_empty() {
             *id = raw_id;
         }
@@ -210,7 +1159,8 @@ fn edit_name(name: &mut NameInput) {
         }
     }
 
-    if let Ok(locale) = readline(&format!("  locale [{}]: ", name.locale)) {
+    let prompt = format!("  locale [{}]: ", name.locale);
+    if let Ok(locale) = editline_with_completion(&prompt, "", Completion::Locale) {
         if !locale.is_empty() {
             name.locale = locale;
         }
@@ -263,6 +1213,31 @@ fn guess_name(names: &mut Vec<NameInput>) -> bool {
     false
 }
 
+fn prompt_position() -> Option<i32> {
+    let input = readline("> Position: ").ok()?;
+    let input = input.trim();
+
+    if input.is_empty() {
+        return None;
+    }
+
+    let position = parse_position(input);
+
+    if position.is_none() {
+        println!("  invalid position: {} (expected 1 or more)", input);
+    }
+
+    position
+}
+
+// Parses a 1-based track position.
+fn parse_position(s: &str) -> Option<i32> {
+    match s.trim().parse() {
+        Ok(n) if n >= 1 => Some(n),
+        _ => None,
+    }
+}
+
 fn prompt_index() -> usize {
     readline("> Index: ")
         .ok()
@@ -295,14 +1270,50 @@ fn parse_boolean(s: &str) -> bool {
     s == "true" || s == "t"
 }
 
+fn parse_date(s: &str) -> Result<String, String> {
+    NaiveDate::parse_from_str(s, "%Y-%m-%d")
+        .map(|_| s.into())
+        .map_err(|_| format!("invalid release date: {} (expected YYYY-MM-DD)", s))
+}
+
+// Parses an ISO 3166-1 alpha-2 country code, e.g., `KR`.
+fn parse_country(s: &str) -> Result<String, String> {
+    if s.len() == 2 && s.chars().all(|c| c.is_ascii_alphabetic()) {
+        Ok(s.to_ascii_uppercase())
+    } else {
+        Err(format!("invalid country: {} (expected a 2-letter code)", s))
+    }
+}
+
+fn parse_url(s: &str) -> Result<String, String> {
+    match Url::parse(s) {
+        Ok(url) if url.scheme() == "http" || url.scheme() == "https" => Ok(s.into()),
+        _ => Err(format!("invalid URL: {}", s)),
+    }
+}
+
+fn parse_artwork_url(s: &str) -> Result<Option<String>, String> {
+    if s == "-" {
+        Ok(None)
+    } else {
+        parse_url(s).map(Some)
+    }
+}
+
 fn readline(prompt: &str) -> rustyline::Result<String> {
-    let mut rl = rustyline::Editor::<()>::new();
-    exit_on_interrupted(rl.readline(prompt))
+    editline_with_completion(prompt, "", Completion::None)
 }
 
 fn editline(prompt: &str, text: &str) -> rustyline::Result<String> {
-    let mut rl = rustyline::Editor::<()>::new();
-    exit_on_interrupted(rl.readline_with_initial(prompt, (text, "")))
+    editline_with_completion(prompt, text, Completion::None)
+}
+
+fn editline_with_completion(
+    prompt: &str,
+    text: &str,
+    completion: Completion,
+) -> rustyline::Result<String> {
+    exit_on_interrupted(prompt::readline(prompt, text, completion))
 }
 
 fn exit_on_interrupted(result: rustyline::Result<String>) -> rustyline::Result<String> {
@@ -316,12 +1327,36 @@ fn exit_on_interrupted(result: rustyline::Result<String>) -> rustyline::Result<S
     }
 }
 
+// Changes a list of names. An ID that was generated from the default name is generated again.
+fn update_names<F>(id: &mut String, names: &mut Vec<NameInput>, f: F)
+where
+    F: FnOnce(&mut Vec<NameInput>),
+{
+    let is_generated = default_name(names)
+        .map(|name| parameterize(&name) == *id)
+        .unwrap_or(false);
+
+    f(names);
+
+    if is_generated {
+        if let Some(name) = default_name(names) {
+            let new_id = parameterize(&name);
+
+            if !new_id.is_empty() {
+                *id = new_id;
+            }
+        }
+    }
+}
+
 fn default_name(names: &[NameInput]) -> Option<String> {
     names.iter().find(|n| n.is_default).map(|n| n.name.clone())
 }
 
 #[cfg(test)]
 mod tests {
+    use crate::models::song;
+
     use super::*;
 
     #[test]
@@ -383,6 +1418,280 @@ mod tests {
         assert!(name.is_default);
     }
 
+    fn build_album(song_names: &[&str]) -> Album {
+        let mut builder = album::Builder::new()
+            .set_id("")
+            .set_kind(album::Kind::Single)
+            .set_country("KR")
+            .set_released_on("2017-12-28")
+            .set_url("https://www.melon.com/album/detail.htm?albumId=10123637")
+            .add_name(Name::new("Chuu", "en", true, true));
+
+        for (i, name) in song_names.iter().enumerate() {
+            let song = song::Builder::new()
+                .set_position(i as i32 + 1)
+                .set_duration(200)
+                .add_name(Name::new(*name, "en", true, true))
+                .build();
+
+            builder = builder.add_song(song);
+        }
+
+        builder.build()
+    }
+
+    #[test]
+    fn test_auto_edit() {
+        let album = build_album(&["Heart Attack", "Love Cherry Motion"]);
+        let album = auto_edit(&album, &Catalog::default(), false).unwrap();
+
+        assert_eq!(album.id(), "chuu");
+        assert_eq!(album.songs[0].id(), "heart-attack");
+        assert_eq!(album.songs[1].id(), "love-cherry-motion");
+
+        let album = build_album(&["Heart Attack", "Heart Attack"]);
+        assert_eq!(
+            auto_edit(&album, &Catalog::default(), false).err(),
+            Some(EditError::DuplicateSongId(String::from("heart-attack")))
+        );
+
+        let album = build_album(&[":("]);
+        assert_eq!(
+            auto_edit(&album, &Catalog::default(), false).err(),
+            Some(EditError::EmptyId(String::from("track 1")))
+        );
+    }
+
+    #[test]
+    fn test_link_existing_songs() {
+        let catalog = Catalog::load("tests/fixtures/fs", "bol4").unwrap();
+
+        let mut form = prepare(&build_album(&["우주를 줄게", "Hard To Love"]));
+        form.songs[0].duration = 274;
+        link_existing_songs(&mut form, &catalog);
+
+        assert_eq!(form.songs[0].id, "uju-reul-jul-ge");
+        assert!(form.songs[0].is_existing);
+        assert!(!form.songs[1].is_existing);
+    }
+
+    #[test]
+    fn test_unique_song_id() {
+        let mut song_ids = HashSet::new();
+        song_ids.insert(String::from("uju-reul-jul-ge"));
+        assert_eq!(
+            unique_song_id("uju-reul-jul-ge", &song_ids),
+            "uju-reul-jul-ge-2"
+        );
+
+        song_ids.insert(String::from("uju-reul-jul-ge-2"));
+        assert_eq!(
+            unique_song_id("uju-reul-jul-ge", &song_ids),
+            "uju-reul-jul-ge-3"
+        );
+    }
+
+    #[test]
+    fn test_link_song() {
+        let mut song_ids = HashSet::new();
+        song_ids.insert(String::from("uju-reul-jul-ge"));
+
+        let mut form = prepare(&build_album(&["Galaxy"]));
+        let song = &mut form.songs[0];
+        song.id = String::from("uju-reul-jul-ge");
+
+        assert!(link_song(song, "uju-reul-jul-ge", false, &song_ids));
+        assert_eq!(song.id, "uju-reul-jul-ge-2");
+        assert!(!song.is_existing);
+
+        assert!(!link_song(song, "uju-reul-jul-ge", false, &song_ids));
+        assert_eq!(song.id, "uju-reul-jul-ge-2");
+
+        assert!(!link_song(song, "uju-reul-jul-ge", true, &song_ids));
+        assert_eq!(song.id, "uju-reul-jul-ge");
+        assert!(song.is_existing);
+    }
+
+    #[test]
+    fn test_relink_changed_songs() {
+        let catalog = Catalog::load("tests/fixtures/fs", "bol4").unwrap();
+
+        let mut form = prepare(&build_album(&["Galaxy", "Hard To Love", "Lonely"]));
+        form.songs[1].is_existing = true;
+        form.songs[2].is_existing = true;
+
+        let song_ids: Vec<String> = form.songs.iter().map(|s| s.id.clone()).collect();
+
+        form.songs[0].id = String::from("uju-reul-jul-ge");
+        form.songs[1].id = String::from("hard-to-love-2");
+        relink_changed_songs(&mut form, &song_ids, &catalog);
+
+        assert!(form.songs[0].is_existing);
+        assert!(!form.songs[1].is_existing);
+        assert!(form.songs[2].is_existing);
+    }
+
+    #[test]
+    fn test_link_variants() {
+        let album = build_album(&["Hard To Love", "Hard To Love (Inst.)", "Lonely (Inst.)"]);
+        let mut form = prepare(&album);
+
+        let song = song::Builder::new()
+            .set_position(4)
+            .set_duration(200)
+            .add_name(Name::new("우주를 줄게 (Acoustic Ver.)", "ko", true, false))
+            .add_name(Name::new("Galaxy (Acoustic Ver.)", "en", false, true))
+            .build();
+
+        form.songs.push(SongInput::from(&song));
+
+        let catalog = Catalog::load("tests/fixtures/fs", "bol4").unwrap();
+        link_variants(&mut form, &catalog);
+
+        assert!(form.songs[0].base_song_id.is_none());
+        assert_eq!(
+            form.songs[1].base_song_id,
+            Some(String::from("hard-to-love"))
+        );
+        assert!(form.songs[2].base_song_id.is_none());
+        assert_eq!(
+            form.songs[3].base_song_id,
+            Some(String::from("uju-reul-jul-ge"))
+        );
+    }
+
+    #[test]
+    fn test_auto_edit_with_guess_names() {
+        let song = song::Builder::new()
+            .set_id("heart-attack")
+            .set_position(1)
+            .set_duration(209)
+            .add_name(Name::new("하트 어택", "ko", true, true))
+            .build();
+
+        let album = album::Builder::new()
+            .set_id("chuu")
+            .set_kind(album::Kind::Single)
+            .set_country("KR")
+            .set_released_on("2017-12-28")
+            .set_url("https://www.melon.com/album/detail.htm?albumId=10123637")
+            .add_name(Name::new("츄", "ko", true, true))
+            .add_song(song)
+            .build();
+
+        let album = auto_edit(&album, &Catalog::default(), true).unwrap();
+
+        assert_eq!(album.id(), "chuu");
+        assert_eq!(album.songs[0].id(), "heart-attack");
+        assert_eq!(album.songs[0].names.len(), 2);
+    }
+
+    fn song_names(songs: &[SongInput]) -> Vec<(i32, &str)> {
+        songs
+            .iter()
+            .map(|s| (s.position, s.names[0].name.as_str()))
+            .collect()
+    }
+
+    #[test]
+    fn test_remove_song() {
+        let mut songs = prepare(&build_album(&["Heart Attack", "Heart Attack (Inst.)"])).songs;
+
+        assert!(remove_song(&mut songs, 2));
+        assert_eq!(song_names(&songs), [(1, "Heart Attack")]);
+
+        assert!(!remove_song(&mut songs, 2));
+    }
+
+    #[test]
+    fn test_insert_song() {
+        let mut album = prepare(&build_album(&["Heart Attack", "Girl's Talk"]));
+        let mut song = album.songs[0].clone();
+        song.position = 2;
+        song.names[0].name = String::from("Hidden Track");
+
+        insert_song(&mut album.songs, song.clone());
+        assert_eq!(
+            song_names(&album.songs),
+            [(1, "Heart Attack"), (2, "Hidden Track"), (3, "Girl's Talk")]
+        );
+
+        song.position = 5;
+        assert!(insert_song(&mut album.songs, song.clone()));
+        assert_eq!(album.songs[3].position, 5);
+
+        song.position = 0;
+        assert!(!insert_song(&mut album.songs, song));
+        assert_eq!(album.songs.len(), 4);
+    }
+
+    #[test]
+    fn test_move_song() {
+        let mut songs = prepare(&build_album(&["Heart Attack", "Girl's Talk", "Intro"])).songs;
+
+        assert!(move_song(&mut songs, 3, 1));
+        assert_eq!(
+            song_names(&songs),
+            [(1, "Intro"), (2, "Heart Attack"), (3, "Girl's Talk")]
+        );
+
+        assert!(move_song(&mut songs, 1, 8));
+        assert_eq!(songs[2].names[0].name, "Intro");
+
+        assert!(!move_song(&mut songs, 4, 1));
+    }
+
+    #[test]
+    fn test_renumber_songs() {
+        let mut songs = prepare(&build_album(&["Heart Attack", "Girl's Talk", "Intro"])).songs;
+
+        remove_song(&mut songs, 1);
+        renumber_songs(&mut songs);
+
+        assert_eq!(song_names(&songs), [(1, "Girl's Talk"), (2, "Intro")]);
+    }
+
+    #[test]
+    fn test_generate_id() {
+        let names = vec![
+            NameInput {
+                name: String::from("Heart Attack"),
+                is_default: true,
+                delete: true,
+                ..NameInput::default()
+            },
+            NameInput {
+                name: String::from("Heart Attack (츄)"),
+                ..NameInput::default()
+            },
+        ];
+
+        assert_eq!(
+            generate_id(&names, || String::from("album")),
+            Err(EditError::MissingDefaultName(String::from("album")))
+        );
+    }
+
+    #[test]
+    fn test_parse_position() {
+        assert_eq!(parse_position("1"), Some(1));
+        assert_eq!(parse_position(" 12 "), Some(12));
+
+        assert!(parse_position("0").is_none());
+        assert!(parse_position("-1").is_none());
+        assert!(parse_position("one").is_none());
+    }
+
+    #[test]
+    fn test_parse_pick() {
+        assert_eq!(parse_pick("1", 2), Some(0));
+        assert_eq!(parse_pick(" 2 ", 2), Some(1));
+
+        assert!(parse_pick("0", 2).is_none());
+        assert!(parse_pick("3", 2).is_none());
+        assert!(parse_pick("a", 2).is_none());
+    }
+
     #[test]
     fn test_parse_boolean() {
         assert!(parse_boolean("true"));
@@ -395,6 +1704,47 @@ mod tests {
         assert!(!parse_boolean("y"));
     }
 
+    #[test]
+    fn test_parse_date() {
+        assert_eq!(parse_date("2017-12-28"), Ok(String::from("2017-12-28")));
+        assert_eq!(
+            parse_date("2017-12-32"),
+            Err(String::from(
+                "invalid release date: 2017-12-32 (expected YYYY-MM-DD)"
+            ))
+        );
+        assert!(parse_date("20171228").is_err());
+    }
+
+    #[test]
+    fn test_parse_country() {
+        assert_eq!(parse_country("KR"), Ok(String::from("KR")));
+        assert_eq!(parse_country("jp"), Ok(String::from("JP")));
+        assert!(parse_country("KOR").is_err());
+        assert!(parse_country("1A").is_err());
+    }
+
+    #[test]
+    fn test_parse_url() {
+        let url = "https://www.melon.com/album/detail.htm?albumId=10123637";
+        assert_eq!(parse_url(url), Ok(String::from(url)));
+        assert_eq!(
+            parse_url("melon.com"),
+            Err(String::from("invalid URL: melon.com"))
+        );
+        assert!(parse_url("ftp://localhost/artwork.jpg").is_err());
+    }
+
+    #[test]
+    fn test_parse_artwork_url() {
+        assert_eq!(
+            parse_artwork_url("http://localhost/artwork.jpg"),
+            Ok(Some(String::from("http://localhost/artwork.jpg")))
+        );
+        assert_eq!(parse_artwork_url("-"), Ok(None));
+        assert!(parse_artwork_url("artwork.jpg").is_err());
+    }
+
     #[test]
     fn test_default_name() {
         let names = vec![
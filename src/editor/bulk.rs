@@ -0,0 +1,283 @@
+use crate::util::{
+    inflector::titleize,
+    script::{primary_script, Script},
+};
+
+use super::{guess_missing_name, update_names, AlbumInput, NameInput};
+
+/// An operation on the names of an album and all of its songs.
+#[derive(Debug, Eq, PartialEq)]
+pub(super) enum Operation {
+    /// Guesses a name, e.g., a romanization, as with `g` in the editor.
+    GuessNames,
+    /// Titleizes romanized names, i.e., names with a `*-Latn` locale.
+    TitleizeRomanizations,
+    /// Sets the locale of names that are written in a script.
+    ///
+    /// A name is in the script that determines its locale (see `primary_script`), e.g., a name
+    /// mixing kanji and kana is in kana.
+    SetLocale { script: Script, locale: String },
+    /// Replaces all occurrences of a string in names.
+    ///
+    /// A name that would become empty is kept.
+    Replace { from: String, to: String },
+}
+
+impl Operation {
+    // It returns the names that were kept since the operation would make them empty.
+    fn apply_to_names(&self, names: &mut Vec<NameInput>) -> Vec<String> {
+        let mut kept_names = Vec::new();
+
+        match self {
+            Self::GuessNames => {
+                guess_missing_name(names);
+            }
+            Self::TitleizeRomanizations => {
+                for name in names_mut(names).filter(|n| n.locale.ends_with("-Latn")) {
+                    name.name = titleize(&name.name);
+                }
+            }
+            Self::SetLocale { script, locale } => {
+                for name in names_mut(names).filter(|n| primary_script(&n.name) == Some(*script)) {
+                    name.locale = locale.clone();
+                }
+            }
+            Self::Replace { from, to } => {
+                if from.is_empty() {
+                    return kept_names;
+                }
+
+                for name in names_mut(names) {
+                    let new_name = name.name.replace(from.as_str(), to);
+
+                    if new_name.trim().is_empty() {
+                        kept_names.push(name.name.clone());
+                    } else {
+                        name.name = new_name;
+                    }
+                }
+            }
+        }
+
+        kept_names
+    }
+}
+
+fn names_mut(names: &mut [NameInput]) -> impl Iterator<Item = &mut NameInput> {
+    names.iter_mut().filter(|n| !n.delete)
+}
+
+/// Applies an operation to the names of the album and all of its songs.
+///
+/// IDs that were generated from the default name are generated again. Names that the operation
+/// would make empty are kept, and it returns a line for each of them, like `diff`.
+pub(super) fn apply(album: &mut AlbumInput, operation: &Operation) -> Vec<String> {
+    let mut lines = Vec::new();
+
+    let mut kept_names = Vec::new();
+    update_names(&mut album.id, &mut album.names, |names| {
+        kept_names = operation.apply_to_names(names)
+    });
+    push_kept_names(&mut lines, "album", &kept_names);
+
+    for song in &mut album.songs {
+        let mut kept_names = Vec::new();
+        update_names(&mut song.id, &mut song.names, |names| {
+            kept_names = operation.apply_to_names(names)
+        });
+
+        let target = format!("track {}", song.position);
+        push_kept_names(&mut lines, &target, &kept_names);
+    }
+
+    lines
+}
+
+fn push_kept_names(lines: &mut Vec<String>, target: &str, names: &[String]) {
+    for name in names {
+        lines.push(format!("{}: {} would be empty, kept", target, name));
+    }
+}
+
+/// Lists the IDs and names that differ between two versions of an album, one per line.
+///
+/// The album and songs are compared in order, so the tracklist must be the same.
+pub(super) fn diff(before: &AlbumInput, after: &AlbumInput) -> Vec<String> {
+    let mut lines = Vec::new();
+
+    diff_target(
+        &mut lines,
+        "album",
+        (&before.id, &before.names),
+        (&after.id, &after.names),
+    );
+
+    for (a, b) in before.songs.iter().zip(&after.songs) {
+        let target = format!("track {}", a.position);
+        diff_target(&mut lines, &target, (&a.id, &a.names), (&b.id, &b.names));
+    }
+
+    lines
+}
+
+fn diff_target(
+    lines: &mut Vec<String>,
+    target: &str,
+    (a_id, a_names): (&str, &[NameInput]),
+    (b_id, b_names): (&str, &[NameInput]),
+) {
+    for (i, b) in b_names.iter().enumerate() {
+        match a_names.get(i) {
+            Some(a) if a.name != b.name || a.locale != b.locale => lines.push(format!(
+                "{}: {} ({}) -> {} ({})",
+                target, a.name, a.locale, b.name, b.locale
+            )),
+            Some(_) => {}
+            None => lines.push(format!("{}: + {} ({})", target, b.name, b.locale)),
+        }
+    }
+
+    if a_id != b_id {
+        lines.push(format!("{}: id: {} -> {}", target, a_id, b_id));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::models::{album, song, Name};
+
+    use super::*;
+
+    fn build_album_input() -> AlbumInput {
+        let songs = [("Heart Attack", "en"), ("girl's talk", "en-Latn")]
+            .iter()
+            .enumerate()
+            .map(|(i, (name, locale))| {
+                song::Builder::new()
+                    .set_position(i as i32 + 1)
+                    .set_duration(209)
+                    .add_name(Name::new(*name, *locale, true, true))
+                    .build()
+            });
+
+        let mut builder = album::Builder::new()
+            .set_kind(album::Kind::Single)
+            .set_country("KR")
+            .set_released_on("2017-12-28")
+            .set_url("https://www.melon.com/album/detail.htm?albumId=10123637")
+            .add_name(Name::new("Chuu", "en", true, true));
+
+        for song in songs {
+            builder = builder.add_song(song);
+        }
+
+        AlbumInput::from(&builder.build())
+    }
+
+    #[test]
+    fn test_apply_with_titleize_romanizations() {
+        let mut album = build_album_input();
+        apply(&mut album, &Operation::TitleizeRomanizations);
+
+        assert_eq!(album.songs[0].names[0].name, "Heart Attack");
+        assert_eq!(album.songs[1].names[0].name, "Girl's Talk");
+        assert_eq!(album.songs[1].id, "girl-s-talk");
+    }
+
+    #[test]
+    fn test_apply_with_set_locale() {
+        let mut album = build_album_input();
+        album.songs[0].names[0].name = String::from("Heart Attack (츄)");
+
+        let operation = Operation::SetLocale {
+            script: Script::Latin,
+            locale: String::from("ko-Latn"),
+        };
+
+        apply(&mut album, &operation);
+
+        assert_eq!(album.names[0].locale, "ko-Latn");
+        assert_eq!(album.songs[0].names[0].locale, "en");
+        assert_eq!(album.songs[1].names[0].locale, "ko-Latn");
+    }
+
+    #[test]
+    fn test_apply_with_set_locale_with_mixed_scripts() {
+        let mut album = build_album_input();
+        album.songs[0].names[0].name = String::from("恋愛レボリューション21");
+        album.songs[1].names[0].name = String::from("恋愛");
+
+        let operation = Operation::SetLocale {
+            script: Script::Kana,
+            locale: String::from("ja"),
+        };
+
+        apply(&mut album, &operation);
+
+        assert_eq!(album.songs[0].names[0].locale, "ja");
+        assert_ne!(album.songs[1].names[0].locale, "ja");
+
+        let operation = Operation::SetLocale {
+            script: Script::Han,
+            locale: String::from("zh"),
+        };
+
+        apply(&mut album, &operation);
+
+        assert_eq!(album.songs[0].names[0].locale, "ja");
+        assert_eq!(album.songs[1].names[0].locale, "zh");
+    }
+
+    #[test]
+    fn test_apply_with_replace() {
+        let mut album = build_album_input();
+        album.songs[0].id = String::from("heart-attack-chuu");
+
+        let operation = Operation::Replace {
+            from: String::from("Attack"),
+            to: String::from("Attack!"),
+        };
+
+        apply(&mut album, &operation);
+
+        assert_eq!(album.songs[0].names[0].name, "Heart Attack!");
+        assert_eq!(album.songs[0].id, "heart-attack-chuu");
+    }
+
+    #[test]
+    fn test_apply_with_replace_with_empty_name() {
+        let mut album = build_album_input();
+
+        let operation = Operation::Replace {
+            from: String::from("Heart Attack"),
+            to: String::new(),
+        };
+
+        let lines = apply(&mut album, &operation);
+
+        assert_eq!(lines, ["track 1: Heart Attack would be empty, kept"]);
+        assert_eq!(album.songs[0].names[0].name, "Heart Attack");
+        assert_eq!(album.songs[0].id, "heart-attack");
+    }
+
+    #[test]
+    fn test_diff() {
+        let before = build_album_input();
+
+        let mut after = before.clone();
+        apply(&mut after, &Operation::TitleizeRomanizations);
+        after.names.push(NameInput {
+            name: String::from("츄"),
+            locale: String::from("ko"),
+            ..NameInput::default()
+        });
+
+        assert_eq!(
+            diff(&before, &after),
+            [
+                "album: + 츄 (ko)",
+                "track 2: girl's talk (en-Latn) -> Girl's Talk (en-Latn)",
+            ]
+        );
+    }
+}
@@ -0,0 +1,464 @@
+use std::{collections::HashSet, env, fs, io, process::Command};
+
+use serde::Deserialize;
+use uuid::Uuid;
+
+use crate::{
+    extractors::Warning,
+    models::album,
+    util::{format_duration, inflector::parameterize},
+};
+
+use super::{parse_country, parse_date, parse_url, AlbumInput, NameInput};
+
+static HEADER: &str = "\
+# Edit the album and its songs. Save and quit to commit, or delete all lines to cancel.
+# Songs are matched by position. Lines starting with '#' are ignored.
+# An existing song is not written again. Change its ID to write it as a new song.
+";
+
+static ERROR_PREFIX: &str = "# error: ";
+
+#[derive(Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+struct Document {
+    id: String,
+    kind: String,
+    released_on: String,
+    country: String,
+    url: String,
+    artwork_url: Option<String>,
+    #[serde(default)]
+    names: Vec<RawName>,
+    #[serde(default)]
+    songs: Vec<RawSong>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawSong {
+    position: i32,
+    id: String,
+    #[serde(default)]
+    names: Vec<RawName>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawName {
+    name: String,
+    locale: String,
+    #[serde(default)]
+    original: bool,
+    #[serde(default)]
+    default: bool,
+}
+
+impl From<RawName> for NameInput {
+    fn from(name: RawName) -> NameInput {
+        NameInput {
+            name: name.name,
+            locale: name.locale,
+            is_original: name.original,
+            is_default: name.default,
+            ..NameInput::default()
+        }
+    }
+}
+
+/// Renders an album as a TOML document to edit.
+///
+/// Deleted names are left out. Warnings and read-only song fields are added as comments.
+pub(super) fn render(album: &AlbumInput, warnings: &[Warning]) -> String {
+    let mut buf = String::from(HEADER);
+
+    for warning in warnings {
+        buf.push_str(&format!("# warning: {}\n", warning));
+    }
+
+    buf.push('\n');
+    buf.push_str(&format!("id = {}\n", quote(&album.id)));
+    buf.push_str(&format!("kind = {}\n", quote(&album.kind.to_string())));
+    buf.push_str(&format!("released-on = {}\n", quote(&album.released_on)));
+    buf.push_str(&format!("country = {}\n", quote(&album.country)));
+    buf.push_str(&format!("url = {}\n", quote(&album.url)));
+
+    if let Some(ref artwork_url) = album.artwork_url {
+        buf.push_str(&format!("artwork-url = {}\n", quote(artwork_url)));
+    }
+
+    render_names(&mut buf, &album.names);
+
+    for song in &album.songs {
+        buf.push_str("\n[[songs]]\n");
+        buf.push_str(&format!(
+            "position = {} # {}\n",
+            song.position,
+            format_duration(song.duration)
+        ));
+        if song.is_existing {
+            buf.push_str(&format!("id = {} # existing song\n", quote(&song.id)));
+        } else {
+            buf.push_str(&format!("id = {}\n", quote(&song.id)));
+        }
+
+        render_names(&mut buf, &song.names);
+    }
+
+    buf
+}
+
+fn render_names(buf: &mut String, names: &[NameInput]) {
+    buf.push_str("names = [\n");
+
+    for name in names.iter().filter(|n| !n.delete) {
+        buf.push_str(&format!(
+            "  {{ name = {}, locale = {}",
+            quote(&name.name),
+            quote(&name.locale)
+        ));
+
+        if name.is_original {
+            buf.push_str(", original = true");
+        }
+
+        if name.is_default {
+            buf.push_str(", default = true");
+        }
+
+        buf.push_str(" },\n");
+    }
+
+    buf.push_str("]\n");
+}
+
+fn quote(s: &str) -> String {
+    toml::Value::String(s.into()).to_string()
+}
+
+/// Parses an edited document and applies it to the album.
+///
+/// The album is only changed if the document is valid. Otherwise, all validation errors are
+/// returned.
+///
+/// An ID that was generated from the default name and left unchanged is generated again from the
+/// new default name.
+pub(super) fn apply(album: &mut AlbumInput, s: &str) -> Result<(), Vec<String>> {
+    let document: Document = toml::from_str(s).map_err(|e| vec![e.to_string()])?;
+
+    let mut errors = Vec::new();
+
+    let kind = check(&mut errors, document.kind.parse::<album::Kind>());
+    let released_on = check(&mut errors, parse_date(&document.released_on));
+    let country = check(&mut errors, parse_country(&document.country));
+    let url = check(&mut errors, parse_url(&document.url));
+    let artwork_url = match document.artwork_url {
+        Some(ref s) => check(&mut errors, parse_url(s).map(Some)),
+        None => Some(None),
+    };
+
+    validate_names(&mut errors, "album", &document.names);
+    validate_id(&mut errors, "album", &document.id);
+
+    let mut positions = HashSet::new();
+    let mut song_ids = HashSet::new();
+
+    for song in &document.songs {
+        let target = format!("track {}", song.position);
+
+        if !album.songs.iter().any(|s| s.position == song.position) {
+            errors.push(format!("{}: no song at this position", target));
+        } else if !positions.insert(song.position) {
+            errors.push(format!("{}: duplicate position", target));
+        }
+
+        validate_names(&mut errors, &target, &song.names);
+        validate_id(&mut errors, &target, &song.id);
+
+        if !song.id.is_empty() && !song_ids.insert(song.id.as_str()) {
+            errors.push(format!("{}: duplicate song ID: {}", target, song.id));
+        }
+    }
+
+    for song in &album.songs {
+        if !positions.contains(&song.position) {
+            errors.push(format!("track {}: missing song", song.position));
+        }
+    }
+
+    if !errors.is_empty() {
+        return Err(errors);
+    }
+
+    if let (Some(kind), Some(released_on), Some(country), Some(url), Some(artwork_url)) =
+        (kind, released_on, country, url, artwork_url)
+    {
+        album.kind = kind;
+        album.released_on = released_on;
+        album.country = country;
+        album.url = url;
+        album.artwork_url = artwork_url;
+    }
+
+    album.id = resolve_id(&album.id, &album.names, document.id, &document.names);
+    album.names = document.names.into_iter().map(NameInput::from).collect();
+
+    for raw_song in document.songs {
+        if let Some(song) = album
+            .songs
+            .iter_mut()
+            .find(|s| s.position == raw_song.position)
+        {
+            song.id = resolve_id(&song.id, &song.names, raw_song.id, &raw_song.names);
+            song.names = raw_song.names.into_iter().map(NameInput::from).collect();
+        }
+    }
+
+    Ok(())
+}
+
+fn check<T>(errors: &mut Vec<String>, result: Result<T, String>) -> Option<T> {
+    result.map_err(|e| errors.push(e)).ok()
+}
+
+fn validate_names(errors: &mut Vec<String>, target: &str, names: &[RawName]) {
+    if names.iter().any(|n| n.name.trim().is_empty()) {
+        errors.push(format!("{}: empty name", target));
+    }
+
+    if names.iter().any(|n| n.locale.trim().is_empty()) {
+        errors.push(format!("{}: empty locale", target));
+    }
+
+    match names.iter().filter(|n| n.default).count() {
+        1 => {}
+        0 => errors.push(format!("{}: missing default name", target)),
+        _ => errors.push(format!("{}: more than one default name", target)),
+    }
+
+    if names.iter().filter(|n| n.original).count() > 1 {
+        errors.push(format!("{}: more than one original name", target));
+    }
+}
+
+fn validate_id(errors: &mut Vec<String>, target: &str, id: &str) {
+    if id.trim().is_empty() {
+        errors.push(format!("{}: empty ID", target));
+    }
+}
+
+fn resolve_id(old_id: &str, old_names: &[NameInput], new_id: String, names: &[RawName]) -> String {
+    let old_default_id = old_names
+        .iter()
+        .find(|n| n.is_default && !n.delete)
+        .map(|n| parameterize(&n.name));
+
+    let is_generated = old_default_id.map(|id| id == old_id).unwrap_or(false);
+
+    if new_id == old_id && is_generated {
+        if let Some(name) = names.iter().find(|n| n.default) {
+            let id = parameterize(&name.name);
+
+            if !id.is_empty() {
+                return id;
+            }
+        }
+    }
+
+    new_id
+}
+
+/// Returns whether a document has no lines other than blank lines and comments.
+pub(super) fn is_empty(s: &str) -> bool {
+    s.lines()
+        .map(str::trim)
+        .all(|l| l.is_empty() || l.starts_with('#'))
+}
+
+/// Replaces the errors at the top of a document with new errors.
+pub(super) fn annotate(s: &str, errors: &[String]) -> String {
+    let mut buf = String::new();
+
+    for error in errors {
+        buf.push_str(ERROR_PREFIX);
+        buf.push_str(error);
+        buf.push('\n');
+    }
+
+    for line in s.lines().filter(|l| !l.starts_with(ERROR_PREFIX)) {
+        buf.push_str(line);
+        buf.push('\n');
+    }
+
+    buf
+}
+
+/// Opens text in the user's editor and returns the saved text.
+///
+/// The editor is taken from `$VISUAL` or `$EDITOR` and defaults to `vi`. It may include
+/// arguments, e.g., `code --wait`.
+pub(super) fn open_editor(text: &str) -> io::Result<String> {
+    let editor = env::var("VISUAL")
+        .or_else(|_| env::var("EDITOR"))
+        .unwrap_or_else(|_| String::from("vi"));
+
+    let mut args = editor.split_whitespace();
+    let program = args
+        .next()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "empty editor command"))?;
+
+    let filename = format!("multimeta-{}.toml", Uuid::new_v4().to_hyphenated_ref());
+    let path = env::temp_dir().join(filename);
+
+    fs::write(&path, text)?;
+
+    let status = Command::new(program).args(args).arg(&path).status();
+    let result = status.and_then(|status| {
+        if status.success() {
+            fs::read_to_string(&path)
+        } else {
+            Err(io::Error::new(
+                io::ErrorKind::Other,
+                format!("editor exited with {}", status),
+            ))
+        }
+    });
+
+    fs::remove_file(&path)?;
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::models::{song, Name};
+
+    use super::*;
+
+    fn build_album_input() -> AlbumInput {
+        let song = song::Builder::new()
+            .set_position(1)
+            .set_duration(209)
+            .add_name(Name::new("Heart Attack (Chuu)", "en", true, true))
+            .build();
+
+        let album = album::Builder::new()
+            .set_kind(album::Kind::Single)
+            .set_country("KR")
+            .set_released_on("2017-12-28")
+            .set_url("https://www.melon.com/album/detail.htm?albumId=10123637")
+            .add_name(Name::new("Chuu", "en", true, true))
+            .add_song(song)
+            .build();
+
+        AlbumInput::from(&album)
+    }
+
+    #[test]
+    fn test_render() {
+        let album = build_album_input();
+        let document = render(&album, &[Warning::MissingArtwork]);
+
+        let expected = format!(
+            "{}# warning: missing artwork
+
+id = \"chuu\"
+kind = \"single\"
+released-on = \"2017-12-28\"
+country = \"KR\"
+url = \"https://www.melon.com/album/detail.htm?albumId=10123637\"
+names = [
+  {{ name = \"Chuu\", locale = \"en\", original = true, default = true }},
+]
+
+[[songs]]
+position = 1 # 3:29
+id = \"heart-attack-chuu\"
+names = [
+  {{ name = \"Heart Attack (Chuu)\", locale = \"en\", original = true, default = true }},
+]
+",
+            HEADER
+        );
+
+        assert_eq!(document, expected);
+    }
+
+    #[test]
+    fn test_apply() {
+        let mut album = build_album_input();
+        let document = render(&album, &[])
+            .replace("kind = \"single\"", "kind = \"ep\"")
+            .replace("country = \"KR\"", "country = \"jp\"")
+            .replace(
+                "{ name = \"Heart Attack (Chuu)\", locale = \"en\", original = true, default = true },",
+                "{ name = \"Heart Attack (Chuu)\", locale = \"en\", original = true },\n  \
+                 { name = \"Heart Attack\", locale = \"en\", default = true },",
+            );
+
+        apply(&mut album, &document).unwrap();
+
+        assert_eq!(album.kind, album::Kind::Ep);
+        assert_eq!(album.country, "JP");
+        assert_eq!(album.id, "chuu");
+
+        let song = &album.songs[0];
+        assert_eq!(song.id, "heart-attack");
+        assert_eq!(song.names.len(), 2);
+        assert!(song.names[1].is_default);
+    }
+
+    #[test]
+    fn test_apply_with_invalid_document() {
+        let mut album = build_album_input();
+
+        let document = render(&album, &[])
+            .replace("kind = \"single\"", "kind = \"album\"")
+            .replace("2017-12-28", "2017-12-32")
+            .replace("\"KR\"", "\"KOR\"")
+            .replace("position = 1", "position = 2");
+
+        let errors = apply(&mut album, &document).unwrap_err();
+
+        assert_eq!(
+            errors,
+            [
+                "invalid album kind: album",
+                "invalid release date: 2017-12-32 (expected YYYY-MM-DD)",
+                "invalid country: KOR (expected a 2-letter code)",
+                "track 2: no song at this position",
+                "track 1: missing song",
+            ]
+        );
+
+        assert_eq!(album.kind, album::Kind::Single);
+
+        let document = render(&album, &[]).replace(", default = true", "");
+        let errors = apply(&mut album, &document).unwrap_err();
+        assert_eq!(
+            errors,
+            [
+                "album: missing default name",
+                "track 1: missing default name"
+            ]
+        );
+
+        assert!(apply(&mut album, "id = ").is_err());
+    }
+
+    #[test]
+    fn test_is_empty() {
+        assert!(is_empty(""));
+        assert!(is_empty(HEADER));
+        assert!(!is_empty("id = \"chuu\""));
+    }
+
+    #[test]
+    fn test_annotate() {
+        let document = annotate(
+            "# error: a\nid = \"\"\n",
+            &[String::from("album: empty ID")],
+        );
+        assert_eq!(document, "# error: album: empty ID\nid = \"\"\n");
+    }
+}
@@ -0,0 +1,307 @@
+use std::{fs, io, path::Path};
+
+use serde::Deserialize;
+
+use super::{generate_id, update_name_flags, AlbumInput, EditError, NameInput};
+
+/// Scripted edits to an album, read from a TOML or JSON file.
+///
+/// ```toml
+/// [album]
+/// id = "chuu"
+/// add-names = [{ name = "Chuu", locale = "ko-Latn", default = true }]
+///
+/// [[songs]]
+/// position = 2
+/// delete-locales = ["en"]
+/// original = 0
+/// ```
+///
+/// Name indices are of the extracted names, as listed by the editor, followed by the added names.
+/// Names are added, then deleted, then flagged. Without an explicit ID, an ID is generated from
+/// the default name when the names change.
+#[derive(Debug, Default, Deserialize, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub struct Edits {
+    #[serde(default)]
+    pub album: Edit,
+    #[serde(default)]
+    pub songs: Vec<SongEdit>,
+}
+
+/// Edits to the ID and names of an album or song.
+#[derive(Debug, Default, Deserialize, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub struct Edit {
+    pub id: Option<String>,
+    #[serde(default)]
+    pub add_names: Vec<NameEdit>,
+    #[serde(default)]
+    pub delete_names: Vec<usize>,
+    #[serde(default)]
+    pub delete_locales: Vec<String>,
+    /// The index of the name to flag as the original name.
+    pub original: Option<usize>,
+    /// The index of the name to flag as the default name.
+    pub default: Option<usize>,
+}
+
+/// Edits to the song at a position in the tracklist.
+#[derive(Debug, Deserialize, PartialEq)]
+pub struct SongEdit {
+    pub position: i32,
+    #[serde(flatten)]
+    pub edit: Edit,
+}
+
+#[derive(Debug, Deserialize, PartialEq)]
+pub struct NameEdit {
+    pub name: String,
+    pub locale: String,
+    #[serde(default)]
+    pub original: bool,
+    #[serde(default)]
+    pub default: bool,
+}
+
+impl<'a> From<&'a NameEdit> for NameInput {
+    fn from(name: &'a NameEdit) -> NameInput {
+        NameInput {
+            name: name.name.clone(),
+            locale: name.locale.clone(),
+            is_original: name.original,
+            is_default: name.default,
+            ..NameInput::default()
+        }
+    }
+}
+
+impl Edits {
+    /// Reads edits from a file.
+    ///
+    /// Files with a `.json` extension are read as JSON, and all others as TOML.
+    pub fn load<P>(src: P) -> io::Result<Edits>
+    where
+        P: AsRef<Path>,
+    {
+        let src = src.as_ref();
+        let data = fs::read_to_string(src)?;
+
+        if src.extension().map(|ext| ext == "json").unwrap_or(false) {
+            Edits::from_json(&data)
+        } else {
+            Edits::from_toml(&data)
+        }
+    }
+
+    pub fn from_toml(s: &str) -> io::Result<Edits> {
+        toml::from_str(s).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+
+    pub fn from_json(s: &str) -> io::Result<Edits> {
+        serde_json::from_str(s).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+
+    pub(super) fn apply(&self, album: &mut AlbumInput) -> Result<(), EditError> {
+        self.album
+            .apply(&mut album.id, &mut album.names, || String::from("album"))?;
+
+        for song_edit in &self.songs {
+            let position = song_edit.position;
+
+            let song = album
+                .songs
+                .iter_mut()
+                .find(|s| s.position == position)
+                .ok_or(EditError::MissingSong(position))?;
+
+            song_edit.edit.apply(&mut song.id, &mut song.names, || {
+                format!("track {}", position)
+            })?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Edit {
+    fn changes_names(&self) -> bool {
+        !self.add_names.is_empty()
+            || !self.delete_names.is_empty()
+            || !self.delete_locales.is_empty()
+            || self.original.is_some()
+            || self.default.is_some()
+    }
+
+    fn apply<F>(
+        &self,
+        id: &mut String,
+        names: &mut Vec<NameInput>,
+        target: F,
+    ) -> Result<(), EditError>
+    where
+        F: Fn() -> String,
+    {
+        let len = names.len() + self.add_names.len();
+
+        let check_index = |index: usize| {
+            if index < len {
+                Ok(index)
+            } else {
+                Err(EditError::InvalidNameIndex {
+                    target: target(),
+                    index,
+                })
+            }
+        };
+
+        for name in &self.add_names {
+            let i = names.len();
+            names.push(NameInput::from(name));
+            update_name_flags(names, i);
+        }
+
+        for &index in &self.delete_names {
+            let i = check_index(index)?;
+            names[i].delete = true;
+        }
+
+        for locale in &self.delete_locales {
+            for name in names.iter_mut().filter(|n| &n.locale == locale) {
+                name.delete = true;
+            }
+        }
+
+        if let Some(index) = self.original {
+            let i = check_index(index)?;
+            names[i].is_original = true;
+            update_name_flags(names, i);
+        }
+
+        if let Some(index) = self.default {
+            let i = check_index(index)?;
+            names[i].is_default = true;
+            update_name_flags(names, i);
+        }
+
+        if let Some(ref new_id) = self.id {
+            *id = new_id.clone();
+        } else if self.changes_names() {
+            *id = generate_id(names, &target)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::models::{album, song, Name};
+
+    use super::*;
+
+    fn build_album_input() -> AlbumInput {
+        let song = song::Builder::new()
+            .set_position(1)
+            .set_duration(200)
+            .add_name(Name::new("Heart Attack (츄)", "en", true, true))
+            .build();
+
+        let album = album::Builder::new()
+            .set_kind(album::Kind::Single)
+            .set_country("KR")
+            .set_released_on("2017-12-28")
+            .set_url("https://www.melon.com/album/detail.htm?albumId=10123637")
+            .add_name(Name::new("Chuu", "en", true, true))
+            .add_song(song)
+            .build();
+
+        AlbumInput::from(&album)
+    }
+
+    #[test]
+    fn test_from_toml() {
+        let edits = Edits::from_toml(
+            r#"
+[album]
+id = "chuu"
+add-names = [{ name = "츄", locale = "ko" }]
+delete-names = [0]
+
+[[songs]]
+position = 1
+default = 1
+"#,
+        )
+        .unwrap();
+
+        assert_eq!(edits.album.id, Some(String::from("chuu")));
+        assert_eq!(edits.album.add_names.len(), 1);
+        assert_eq!(edits.album.delete_names, [0]);
+        assert_eq!(edits.songs[0].position, 1);
+        assert_eq!(edits.songs[0].edit.default, Some(1));
+
+        assert_eq!(Edits::from_toml("").unwrap(), Edits::default());
+        assert!(Edits::from_toml("[album]\nid = 1").is_err());
+    }
+
+    #[test]
+    fn test_from_json() {
+        let edits =
+            Edits::from_json(r#"{"songs":[{"position":1,"delete-locales":["en"]}]}"#).unwrap();
+        assert_eq!(edits.songs[0].edit.delete_locales, ["en"]);
+    }
+
+    #[test]
+    fn test_apply() {
+        let mut album = build_album_input();
+
+        let edits = Edits::from_toml(
+            r#"
+[album]
+id = "chuu-single"
+
+[[songs]]
+position = 1
+add-names = [{ name = "Heart Attack", locale = "en", default = true }]
+delete-names = [0]
+"#,
+        )
+        .unwrap();
+
+        edits.apply(&mut album).unwrap();
+
+        assert_eq!(album.id, "chuu-single");
+
+        let song = &album.songs[0];
+        assert_eq!(song.id, "heart-attack");
+        assert!(song.names[0].delete);
+        assert!(!song.names[0].is_default);
+        assert!(song.names[0].is_original);
+        assert!(song.names[1].is_default);
+    }
+
+    #[test]
+    fn test_apply_with_invalid_edits() {
+        let mut album = build_album_input();
+        let edits = Edits::from_toml("[album]\ndefault = 2").unwrap();
+        assert_eq!(
+            edits.apply(&mut album),
+            Err(EditError::InvalidNameIndex {
+                target: String::from("album"),
+                index: 2,
+            })
+        );
+
+        let mut album = build_album_input();
+        let edits = Edits::from_toml("[[songs]]\nposition = 2").unwrap();
+        assert_eq!(edits.apply(&mut album), Err(EditError::MissingSong(2)));
+
+        let mut album = build_album_input();
+        let edits = Edits::from_toml("[album]\ndelete-locales = [\"en\"]").unwrap();
+        assert_eq!(
+            edits.apply(&mut album),
+            Err(EditError::MissingDefaultName(String::from("album")))
+        );
+    }
+}
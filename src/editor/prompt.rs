@@ -0,0 +1,225 @@
+use std::{
+    cell::RefCell,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use log::warn;
+use rustyline::{
+    completion::Completer, highlight::Highlighter, hint::Hinter, validate::Validator, Context,
+    Editor, Helper,
+};
+
+static LOCALES: [&str; 9] = [
+    "en", "ja", "ja-Latn", "ko", "ko-Latn", "zh", "zh-Hans", "zh-Hant", "zh-Latn",
+];
+
+thread_local! {
+    static EDITOR: RefCell<Editor<PromptHelper>> = RefCell::new(build_editor());
+}
+
+/// The values to complete at a prompt.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(super) enum Completion {
+    None,
+    Locale,
+    ArtistId,
+    SongId,
+}
+
+struct PromptHelper {
+    completion: Completion,
+    artist_ids: Vec<String>,
+    song_ids: Vec<String>,
+    history_file: Option<PathBuf>,
+}
+
+impl PromptHelper {
+    fn candidates(&self) -> Vec<&str> {
+        match self.completion {
+            Completion::None => Vec::new(),
+            Completion::Locale => LOCALES.to_vec(),
+            Completion::ArtistId => self.artist_ids.iter().map(String::as_str).collect(),
+            Completion::SongId => self.song_ids.iter().map(String::as_str).collect(),
+        }
+    }
+}
+
+impl Completer for PromptHelper {
+    type Candidate = String;
+
+    fn complete(
+        &self,
+        line: &str,
+        pos: usize,
+        _: &Context<'_>,
+    ) -> rustyline::Result<(usize, Vec<String>)> {
+        Ok(complete(&self.candidates(), line, pos))
+    }
+}
+
+impl Hinter for PromptHelper {
+    type Hint = String;
+}
+
+impl Highlighter for PromptHelper {}
+
+impl Validator for PromptHelper {}
+
+impl Helper for PromptHelper {}
+
+fn build_editor() -> Editor<PromptHelper> {
+    let mut editor = Editor::new();
+
+    editor.set_helper(Some(PromptHelper {
+        completion: Completion::None,
+        artist_ids: Vec::new(),
+        song_ids: Vec::new(),
+        history_file: None,
+    }));
+
+    editor
+}
+
+// Completes the word before the cursor. It returns the start of the word and the sorted
+// candidates that begin with it.
+fn complete(candidates: &[&str], line: &str, pos: usize) -> (usize, Vec<String>) {
+    let start = line[..pos]
+        .rfind(char::is_whitespace)
+        .map(|i| i + 1)
+        .unwrap_or(0);
+
+    let prefix = &line[start..pos];
+
+    let mut matches: Vec<String> = candidates
+        .iter()
+        .filter(|c| c.starts_with(prefix))
+        .map(|c| c.to_string())
+        .collect();
+
+    matches.sort();
+    matches.dedup();
+
+    (start, matches)
+}
+
+/// Loads the prompt history from a file and saves it there after every prompt.
+///
+/// A missing file is not an error.
+pub fn set_history_file<P>(path: P)
+where
+    P: AsRef<Path>,
+{
+    let path = path.as_ref();
+
+    EDITOR.with(|editor| {
+        let mut editor = editor.borrow_mut();
+
+        if path.exists() {
+            if let Err(e) = editor.load_history(path) {
+                warn!("failed to read history: {}", e);
+            }
+        }
+
+        if let Some(helper) = editor.helper_mut() {
+            helper.history_file = Some(path.into());
+        }
+    });
+}
+
+/// Sets the artist IDs to complete, e.g., the artists in the output directory.
+pub fn set_artist_ids<I>(ids: I)
+where
+    I: IntoIterator<Item = String>,
+{
+    EDITOR.with(|editor| {
+        if let Some(helper) = editor.borrow_mut().helper_mut() {
+            helper.artist_ids = ids.into_iter().collect();
+        }
+    });
+}
+
+// Song IDs are only unique per artist, since tracklists refer to `<artist-id>/<song-id>`, so only
+// the songs of the album's artist are completed.
+pub(super) fn set_song_ids<I>(ids: I)
+where
+    I: IntoIterator<Item = String>,
+{
+    EDITOR.with(|editor| {
+        if let Some(helper) = editor.borrow_mut().helper_mut() {
+            helper.song_ids = ids.into_iter().collect();
+        }
+    });
+}
+
+/// Reads a line with the given initial text.
+///
+/// Inputs longer than one character are added to the history, i.e., single key commands are not.
+pub(super) fn readline(
+    prompt: &str,
+    initial: &str,
+    completion: Completion,
+) -> rustyline::Result<String> {
+    EDITOR.with(|editor| {
+        let mut editor = editor.borrow_mut();
+
+        let history_file = match editor.helper_mut() {
+            Some(helper) => {
+                helper.completion = completion;
+                helper.history_file.clone()
+            }
+            None => None,
+        };
+
+        let input = editor.readline_with_initial(prompt, (initial, ""))?;
+
+        if input.chars().count() > 1 && editor.add_history_entry(input.as_str()) {
+            if let Some(path) = history_file {
+                save_history(&mut editor, &path);
+            }
+        }
+
+        Ok(input)
+    })
+}
+
+fn save_history(editor: &mut Editor<PromptHelper>, path: &Path) {
+    if let Some(dir) = path.parent() {
+        if let Err(e) = fs::create_dir_all(dir) {
+            warn!("failed to create history directory: {}", e);
+            return;
+        }
+    }
+
+    if let Err(e) = editor.save_history(path) {
+        warn!("failed to write history: {}", e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_complete() {
+        assert_eq!(
+            complete(&LOCALES, "ko", 2),
+            (0, vec![String::from("ko"), String::from("ko-Latn")])
+        );
+
+        assert_eq!(
+            complete(&["loona/chuu", "loona", "bol4"], "lo", 2),
+            (0, vec![String::from("loona"), String::from("loona/chuu")])
+        );
+
+        assert_eq!(
+            complete(&LOCALES, "zh-Hant", 4),
+            (0, vec![String::from("zh-Hans"), String::from("zh-Hant")])
+        );
+        assert_eq!(
+            complete(&LOCALES, "en j", 4),
+            (3, vec![String::from("ja"), String::from("ja-Latn")])
+        );
+        assert!(complete(&LOCALES, "fr", 2).1.is_empty());
+    }
+}
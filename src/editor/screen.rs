@@ -0,0 +1,737 @@
+use std::io::{self, Stdout};
+
+use crossterm::{
+    event::{self, Event, KeyCode, KeyEvent, KeyModifiers},
+    execute,
+    terminal::{self, EnterAlternateScreen, LeaveAlternateScreen},
+};
+use tui::{
+    backend::{Backend, CrosstermBackend},
+    layout::{Constraint, Direction, Layout},
+    style::{Modifier, Style},
+    widgets::{Block, Borders, Cell, Paragraph, Row, Table, TableState, Wrap},
+    Frame, Terminal,
+};
+
+use crate::{extractors::Warning, models::Album, renderer::Renderer, util::format_duration};
+
+use super::{guess_missing_name, update_name_flags, update_names, AlbumInput, NameInput, Target};
+
+static HELP: &str = "\
+j/k: row  h/l: column  enter: edit/toggle  a: add name  d: delete  g: guess  G: guess all  \
+p: preview  s: save  q: quit  *: existing song";
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum Column {
+    Id,
+    Name,
+    Locale,
+    Original,
+    Default,
+}
+
+static COLUMNS: [Column; 5] = [
+    Column::Id,
+    Column::Name,
+    Column::Locale,
+    Column::Original,
+    Column::Default,
+];
+
+/// A row of the table, i.e., a name of the album or a song.
+///
+/// A target without names has a single row without a name index.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+struct Line {
+    target: Target,
+    name_index: Option<usize>,
+}
+
+#[derive(Debug, Eq, PartialEq)]
+enum Action {
+    Continue,
+    Save,
+    Quit,
+}
+
+struct App<'a> {
+    form: AlbumInput,
+    // The form before any edits, to confirm quitting with unsaved changes.
+    initial_form: AlbumInput,
+    warnings: &'a [Warning],
+    artist_id: &'a str,
+    renderer: Renderer,
+
+    row: usize,
+    column: usize,
+    // The text of the cell being edited.
+    input: Option<String>,
+    show_preview: bool,
+    status: String,
+    is_quitting: bool,
+}
+
+impl<'a> App<'a> {
+    fn new(form: AlbumInput, warnings: &'a [Warning], artist_id: &'a str) -> App<'a> {
+        App {
+            initial_form: form.clone(),
+            form,
+            warnings,
+            artist_id,
+            renderer: Renderer::new(),
+
+            row: 0,
+            column: 1,
+            input: None,
+            show_preview: true,
+            status: String::new(),
+            is_quitting: false,
+        }
+    }
+
+    fn lines(&self) -> Vec<Line> {
+        let mut lines = Vec::new();
+
+        let mut push_lines = |target, names: &[NameInput]| {
+            if names.is_empty() {
+                lines.push(Line {
+                    target,
+                    name_index: None,
+                });
+            }
+
+            for i in 0..names.len() {
+                lines.push(Line {
+                    target,
+                    name_index: Some(i),
+                });
+            }
+        };
+
+        push_lines(Target::Album, &self.form.names);
+
+        for (i, song) in self.form.songs.iter().enumerate() {
+            push_lines(Target::Song(i), &song.names);
+        }
+
+        lines
+    }
+
+    fn line(&self) -> Line {
+        let lines = self.lines();
+        lines[self.row.min(lines.len() - 1)]
+    }
+
+    fn column(&self) -> Column {
+        COLUMNS[self.column]
+    }
+
+    fn target_mut(&mut self, target: Target) -> (&mut String, &mut Vec<NameInput>) {
+        match target {
+            Target::Album => (&mut self.form.id, &mut self.form.names),
+            Target::Song(i) => {
+                let song = &mut self.form.songs[i];
+                (&mut song.id, &mut song.names)
+            }
+        }
+    }
+
+    fn edit_names<F>(&mut self, target: Target, f: F)
+    where
+        F: FnOnce(&mut Vec<NameInput>),
+    {
+        let (id, names) = self.target_mut(target);
+        update_names(id, names, f);
+    }
+
+    fn cell_text(&self, line: Line, column: Column) -> String {
+        let (id, names) = match line.target {
+            Target::Album => (&self.form.id, &self.form.names),
+            Target::Song(i) => (&self.form.songs[i].id, &self.form.songs[i].names),
+        };
+
+        let name = line.name_index.map(|i| &names[i]);
+
+        match column {
+            Column::Id => id.clone(),
+            Column::Name => name.map(|n| n.name.clone()).unwrap_or_default(),
+            Column::Locale => name.map(|n| n.locale.clone()).unwrap_or_default(),
+            Column::Original => name.map(|n| n.is_original.to_string()).unwrap_or_default(),
+            Column::Default => name.map(|n| n.is_default.to_string()).unwrap_or_default(),
+        }
+    }
+
+    fn handle_key(&mut self, key: KeyEvent) -> Action {
+        if self.input.is_some() {
+            self.handle_input_key(key);
+            return Action::Continue;
+        }
+
+        self.status.clear();
+
+        let is_quitting = self.is_quitting;
+        self.is_quitting = false;
+
+        let row_count = self.lines().len();
+
+        match key.code {
+            KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                return Action::Quit
+            }
+            KeyCode::Char('q') | KeyCode::Esc => return self.quit(is_quitting),
+            KeyCode::Char('s') => return self.save(),
+            KeyCode::Down | KeyCode::Char('j') => self.row = (self.row + 1).min(row_count - 1),
+            KeyCode::Up | KeyCode::Char('k') => self.row = self.row.saturating_sub(1),
+            KeyCode::Right | KeyCode::Char('l') => {
+                self.column = (self.column + 1).min(COLUMNS.len() - 1)
+            }
+            KeyCode::Left | KeyCode::Char('h') => self.column = self.column.saturating_sub(1),
+            KeyCode::Enter | KeyCode::Char('e') | KeyCode::Char(' ') => self.activate(),
+            KeyCode::Char('a') => self.add_name(),
+            KeyCode::Char('d') => self.delete_name(),
+            KeyCode::Char('g') => {
+                let target = self.line().target;
+                self.guess(target);
+            }
+            KeyCode::Char('G') => self.guess_all(),
+            KeyCode::Char('p') => self.show_preview = !self.show_preview,
+            _ => {}
+        }
+
+        Action::Continue
+    }
+
+    fn handle_input_key(&mut self, key: KeyEvent) {
+        let input = match self.input.as_mut() {
+            Some(input) => input,
+            None => return,
+        };
+
+        match key.code {
+            KeyCode::Char(c) => input.push(c),
+            KeyCode::Backspace => {
+                input.pop();
+            }
+            KeyCode::Esc => self.input = None,
+            KeyCode::Enter => {
+                if let Some(text) = self.input.take() {
+                    self.set_text(text);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    // Starts editing a text cell or toggles a flag.
+    fn activate(&mut self) {
+        let line = self.line();
+        let column = self.column();
+
+        match column {
+            Column::Id => self.input = Some(self.cell_text(line, column)),
+            Column::Name | Column::Locale if line.name_index.is_some() => {
+                self.input = Some(self.cell_text(line, column))
+            }
+            Column::Original | Column::Default => {
+                if let Some(i) = line.name_index {
+                    self.edit_names(line.target, |names| {
+                        let name = &mut names[i];
+
+                        if column == Column::Original {
+                            name.is_original = !name.is_original;
+                        } else {
+                            name.is_default = !name.is_default;
+                        }
+
+                        update_name_flags(names, i);
+                    });
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn set_text(&mut self, text: String) {
+        let line = self.line();
+
+        match self.column() {
+            Column::Id if !text.is_empty() => *self.target_mut(line.target).0 = text,
+            Column::Name => {
+                if let Some(i) = line.name_index {
+                    self.edit_names(line.target, |names| names[i].name = text);
+                }
+            }
+            Column::Locale => {
+                if let Some(i) = line.name_index {
+                    self.target_mut(line.target).1[i].locale = text;
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn add_name(&mut self) {
+        let line = self.line();
+        let (_, names) = self.target_mut(line.target);
+        let i = names.len();
+
+        names.push(NameInput::default());
+
+        self.row = self
+            .lines()
+            .iter()
+            .position(|l| l.target == line.target && l.name_index == Some(i))
+            .unwrap_or(self.row);
+        self.column = 1;
+        self.input = Some(String::new());
+    }
+
+    fn delete_name(&mut self) {
+        let line = self.line();
+
+        if let Some(i) = line.name_index {
+            let (_, names) = self.target_mut(line.target);
+            names[i].delete = !names[i].delete;
+        }
+    }
+
+    fn guess(&mut self, target: Target) -> bool {
+        let mut is_guessed = false;
+        self.edit_names(target, |names| is_guessed = guess_missing_name(names));
+        is_guessed
+    }
+
+    fn guess_all(&mut self) {
+        let mut count = 0;
+
+        if self.guess(Target::Album) {
+            count += 1;
+        }
+
+        for i in 0..self.form.songs.len() {
+            if self.guess(Target::Song(i)) {
+                count += 1;
+            }
+        }
+
+        self.status = format!("guessed {} name(s)", count);
+    }
+
+    // Quits unless the form changed, in which case quitting must be confirmed by quitting again.
+    fn quit(&mut self, is_confirmed: bool) -> Action {
+        if is_confirmed || self.form == self.initial_form {
+            return Action::Quit;
+        }
+
+        self.is_quitting = true;
+        self.status = String::from("discard unsaved changes? press q again to quit");
+
+        Action::Continue
+    }
+
+    fn save(&mut self) -> Action {
+        match self.validate() {
+            Ok(()) => Action::Save,
+            Err(e) => {
+                self.status = e;
+                Action::Continue
+            }
+        }
+    }
+
+    // Checks that the album and all songs have an ID and a default name and that no name or locale
+    // is empty.
+    fn validate(&self) -> Result<(), String> {
+        let mut targets = vec![(String::from("album"), &self.form.id, &self.form.names)];
+
+        for song in &self.form.songs {
+            targets.push((format!("track {}", song.position), &song.id, &song.names));
+        }
+
+        for (target, id, names) in targets {
+            if id.is_empty() {
+                return Err(format!("{}: empty ID", target));
+            }
+
+            for name in names.iter().filter(|n| !n.delete) {
+                if name.name.trim().is_empty() {
+                    return Err(format!("{}: empty name", target));
+                }
+
+                if name.locale.trim().is_empty() {
+                    return Err(format!("{}: empty locale", target));
+                }
+            }
+
+            if !names.iter().any(|n| n.is_default && !n.delete) {
+                return Err(format!("{}: missing default name", target));
+            }
+        }
+
+        Ok(())
+    }
+
+    fn preview(&self) -> String {
+        if let Err(e) = self.validate() {
+            return e;
+        }
+
+        let album = Album::from(self.form.clone());
+        let mut buf = self.renderer.render_album(self.artist_id, &album);
+
+        if let Target::Song(i) = self.line().target {
+            buf.push('\n');
+            buf.push_str(&self.renderer.render_song(self.artist_id, &album.songs[i]));
+        }
+
+        buf
+    }
+
+    fn draw<B>(&self, f: &mut Frame<'_, B>)
+    where
+        B: Backend,
+    {
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Min(3), Constraint::Length(3)].as_ref())
+            .split(f.size());
+
+        let body = if self.show_preview {
+            Layout::default()
+                .direction(Direction::Horizontal)
+                .constraints([Constraint::Percentage(60), Constraint::Percentage(40)].as_ref())
+                .split(chunks[0])
+        } else {
+            vec![chunks[0]]
+        };
+
+        let title = format!(
+            "{} ({}, {})",
+            self.form.id, self.form.kind, self.form.released_on
+        );
+
+        let widths = [
+            Constraint::Length(10),
+            Constraint::Percentage(25),
+            Constraint::Percentage(35),
+            Constraint::Length(8),
+            Constraint::Length(8),
+            Constraint::Length(8),
+        ];
+
+        let table = Table::new(self.rows())
+            .header(
+                Row::new(vec!["", "id", "name", "locale", "original", "default"])
+                    .style(Style::default().add_modifier(Modifier::BOLD)),
+            )
+            .block(Block::default().borders(Borders::ALL).title(title))
+            .widths(&widths)
+            .highlight_style(Style::default().add_modifier(Modifier::BOLD));
+
+        let mut state = TableState::default();
+        state.select(Some(self.row));
+        f.render_stateful_widget(table, body[0], &mut state);
+
+        if self.show_preview {
+            let preview = Paragraph::new(self.preview())
+                .block(Block::default().borders(Borders::ALL).title("preview"))
+                .wrap(Wrap { trim: false });
+            f.render_widget(preview, body[1]);
+        }
+
+        let status = match self.input {
+            Some(ref input) => format!("{}: {}_", column_name(self.column()), input),
+            None if !self.status.is_empty() => self.status.clone(),
+            None => match self.warnings.len() {
+                0 => String::from(HELP),
+                n => format!("{} warning(s): {}", n, self.warnings[0]),
+            },
+        };
+
+        let status = Paragraph::new(status).block(Block::default().borders(Borders::ALL));
+        f.render_widget(status, chunks[1]);
+    }
+
+    fn rows(&self) -> Vec<Row<'static>> {
+        let lines = self.lines();
+
+        lines
+            .iter()
+            .enumerate()
+            .map(|(row, &line)| {
+                let is_first = line.name_index.map(|i| i == 0).unwrap_or(true);
+
+                let label = match line.target {
+                    Target::Album if is_first => String::from("album"),
+                    Target::Song(i) if is_first => {
+                        let song = &self.form.songs[i];
+                        let marker = if song.is_existing { " *" } else { "" };
+                        format!(
+                            "{} {}{}",
+                            song.position,
+                            format_duration(song.duration),
+                            marker
+                        )
+                    }
+                    _ => String::new(),
+                };
+
+                let mut cells = vec![Cell::from(label)];
+
+                for (column_index, &column) in COLUMNS.iter().enumerate() {
+                    let text = if column == Column::Id && !is_first {
+                        String::new()
+                    } else {
+                        self.cell_text(line, column)
+                    };
+
+                    let mut style = Style::default();
+
+                    if row == self.row && column_index == self.column {
+                        style = style.add_modifier(Modifier::REVERSED);
+                    }
+
+                    let is_deleted = line
+                        .name_index
+                        .map(|i| self.names(line.target)[i].delete)
+                        .unwrap_or(false);
+
+                    if is_deleted && column != Column::Id {
+                        style = style.add_modifier(Modifier::CROSSED_OUT);
+                    }
+
+                    cells.push(Cell::from(text).style(style));
+                }
+
+                Row::new(cells)
+            })
+            .collect()
+    }
+
+    fn names(&self, target: Target) -> &[NameInput] {
+        match target {
+            Target::Album => &self.form.names,
+            Target::Song(i) => &self.form.songs[i].names,
+        }
+    }
+}
+
+fn column_name(column: Column) -> &'static str {
+    match column {
+        Column::Id => "id",
+        Column::Name => "name",
+        Column::Locale => "locale",
+        Column::Original => "original",
+        Column::Default => "default",
+    }
+}
+
+// Restores the terminal when dropped.
+struct Screen {
+    terminal: Terminal<CrosstermBackend<Stdout>>,
+}
+
+impl Screen {
+    fn enter() -> io::Result<Screen> {
+        terminal::enable_raw_mode().map_err(to_io_error)?;
+
+        let mut stdout = io::stdout();
+        execute!(stdout, EnterAlternateScreen).map_err(to_io_error)?;
+
+        let terminal = Terminal::new(CrosstermBackend::new(stdout))?;
+
+        Ok(Screen { terminal })
+    }
+}
+
+impl Drop for Screen {
+    fn drop(&mut self) {
+        let _ = terminal::disable_raw_mode();
+        let _ = execute!(self.terminal.backend_mut(), LeaveAlternateScreen);
+        let _ = self.terminal.show_cursor();
+    }
+}
+
+fn to_io_error(e: crossterm::ErrorKind) -> io::Error {
+    match e {
+        crossterm::ErrorKind::IoError(e) => e,
+        e => io::Error::new(io::ErrorKind::Other, e),
+    }
+}
+
+/// Runs the full-screen editor and returns the edited album or `None` if it is quit.
+pub(super) fn run(
+    form: AlbumInput,
+    warnings: &[Warning],
+    artist_id: &str,
+) -> io::Result<Option<AlbumInput>> {
+    let mut app = App::new(form, warnings, artist_id);
+    let mut screen = Screen::enter()?;
+
+    loop {
+        screen.terminal.draw(|f| app.draw(f))?;
+
+        if let Event::Key(key) = event::read().map_err(to_io_error)? {
+            match app.handle_key(key) {
+                Action::Continue => {}
+                Action::Save => return Ok(Some(app.form)),
+                Action::Quit => return Ok(None),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::models::{album, song, Name};
+
+    use super::*;
+
+    fn build_album_input() -> AlbumInput {
+        let songs = ["Heart Attack", "Girl's Talk"]
+            .iter()
+            .enumerate()
+            .map(|(i, name)| {
+                song::Builder::new()
+                    .set_position(i as i32 + 1)
+                    .set_duration(209)
+                    .add_name(Name::new(*name, "en", true, true))
+                    .build()
+            });
+
+        let mut builder = album::Builder::new()
+            .set_kind(album::Kind::Single)
+            .set_country("KR")
+            .set_released_on("2017-12-28")
+            .set_url("https://www.melon.com/album/detail.htm?albumId=10123637")
+            .add_name(Name::new("Chuu", "en", true, true));
+
+        for song in songs {
+            builder = builder.add_song(song);
+        }
+
+        AlbumInput::from(&builder.build())
+    }
+
+    fn press(app: &mut App<'_>, keys: &str) {
+        for c in keys.chars() {
+            let code = match c {
+                '\n' => KeyCode::Enter,
+                '\u{1b}' => KeyCode::Esc,
+                '\u{8}' => KeyCode::Backspace,
+                _ => KeyCode::Char(c),
+            };
+
+            app.handle_key(KeyEvent::new(code, KeyModifiers::NONE));
+        }
+    }
+
+    #[test]
+    fn test_lines() {
+        let app = App::new(build_album_input(), &[], "loona/chuu");
+        let lines = app.lines();
+
+        assert_eq!(lines.len(), 3);
+        assert_eq!(
+            lines[1],
+            Line {
+                target: Target::Song(0),
+                name_index: Some(0),
+            }
+        );
+    }
+
+    #[test]
+    fn test_handle_key() {
+        let mut app = App::new(build_album_input(), &[], "loona/chuu");
+
+        // Rename the first song, which also regenerates its ID.
+        press(&mut app, "je\u{8}\u{8}\u{8}\u{8}\u{8}\u{8}Attack!\n");
+        assert_eq!(app.form.songs[0].names[0].name, "Heart Attack!");
+        assert_eq!(app.form.songs[0].id, "heart-attack");
+
+        press(&mut app, "e\u{8}\u{8}\u{8}\u{8}\u{8}\u{8}\u{8}Love\n");
+        assert_eq!(app.form.songs[0].id, "heart-love");
+
+        // Add a name and make it the default.
+        press(&mut app, "aHeart Attack (Chuu)\nleen\nll ");
+        let names = &app.form.songs[0].names;
+        assert_eq!(names.len(), 2);
+        assert_eq!(names[1].locale, "en");
+        assert!(names[1].is_default);
+        assert!(!names[0].is_default);
+        assert_eq!(app.form.songs[0].id, "heart-attack-chuu");
+
+        // Delete it, which leaves the song without a default name.
+        press(&mut app, "d");
+        assert!(app.form.songs[0].names[1].delete);
+        assert_eq!(
+            app.handle_key(KeyEvent::new(KeyCode::Char('s'), KeyModifiers::NONE)),
+            Action::Continue
+        );
+        assert_eq!(app.status, "track 1: missing default name");
+
+        press(&mut app, "d");
+        assert_eq!(
+            app.handle_key(KeyEvent::new(KeyCode::Char('s'), KeyModifiers::NONE)),
+            Action::Save
+        );
+    }
+
+    #[test]
+    fn test_handle_key_with_empty_name() {
+        let mut app = App::new(build_album_input(), &[], "loona/chuu");
+
+        // Add a name but cancel its input.
+        press(&mut app, "a\u{1b}");
+        assert_eq!(app.form.names.len(), 2);
+        assert_eq!(
+            app.handle_key(KeyEvent::new(KeyCode::Char('s'), KeyModifiers::NONE)),
+            Action::Continue
+        );
+        assert_eq!(app.status, "album: empty name");
+
+        press(&mut app, "eChuu\n");
+        assert_eq!(
+            app.handle_key(KeyEvent::new(KeyCode::Char('s'), KeyModifiers::NONE)),
+            Action::Continue
+        );
+        assert_eq!(app.status, "album: empty locale");
+    }
+
+    #[test]
+    fn test_quit() {
+        let mut app = App::new(build_album_input(), &[], "loona/chuu");
+        assert_eq!(
+            app.handle_key(KeyEvent::new(KeyCode::Char('q'), KeyModifiers::NONE)),
+            Action::Quit
+        );
+
+        // Quitting with unsaved changes is confirmed by quitting again.
+        press(&mut app, "d");
+        assert_eq!(
+            app.handle_key(KeyEvent::new(KeyCode::Char('q'), KeyModifiers::NONE)),
+            Action::Continue
+        );
+        assert_eq!(
+            app.handle_key(KeyEvent::new(KeyCode::Esc, KeyModifiers::NONE)),
+            Action::Quit
+        );
+
+        // Any other key cancels quitting.
+        press(&mut app, "qj");
+        assert_eq!(
+            app.handle_key(KeyEvent::new(KeyCode::Char('q'), KeyModifiers::NONE)),
+            Action::Continue
+        );
+    }
+
+    #[test]
+    fn test_preview() {
+        let mut app = App::new(build_album_input(), &[], "loona/chuu");
+        assert!(app.preview().contains("name = \"Chuu\""));
+
+        press(&mut app, "j");
+        assert!(app.preview().contains("name = \"Heart Attack\""));
+    }
+}
@@ -0,0 +1,216 @@
+use std::{
+    fs, io, mem,
+    path::{Path, PathBuf},
+};
+
+use log::warn;
+use sha2::{Digest, Sha256};
+
+use super::AlbumInput;
+
+/// An album being edited, with its undo history and autosave.
+pub(super) struct Session {
+    pub form: AlbumInput,
+    undo_stack: Vec<AlbumInput>,
+    redo_stack: Vec<AlbumInput>,
+    autosave: Option<Autosave>,
+}
+
+impl Session {
+    pub fn new(form: AlbumInput, autosave: Option<Autosave>) -> Session {
+        Session {
+            form,
+            undo_stack: Vec::new(),
+            redo_stack: Vec::new(),
+            autosave,
+        }
+    }
+
+    /// Records a change from a previous version of the form and autosaves the form.
+    ///
+    /// Nothing is recorded if the form is unchanged.
+    pub fn record(&mut self, before: AlbumInput) {
+        if before == self.form {
+            return;
+        }
+
+        self.undo_stack.push(before);
+        self.redo_stack.clear();
+        self.save();
+    }
+
+    /// Reverts the last recorded change. It returns `false` if there is nothing to undo.
+    pub fn undo(&mut self) -> bool {
+        match self.undo_stack.pop() {
+            Some(form) => {
+                let current = mem::replace(&mut self.form, form);
+                self.redo_stack.push(current);
+                self.save();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Reapplies the last undone change. It returns `false` if there is nothing to redo.
+    pub fn redo(&mut self) -> bool {
+        match self.redo_stack.pop() {
+            Some(form) => {
+                let current = mem::replace(&mut self.form, form);
+                self.undo_stack.push(current);
+                self.save();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Ends the session, removing its autosave, and returns the form.
+    pub fn finish(self) -> AlbumInput {
+        if let Some(ref autosave) = self.autosave {
+            if let Err(e) = autosave.remove() {
+                warn!("failed to remove autosave: {}", e);
+            }
+        }
+
+        self.form
+    }
+
+    fn save(&self) {
+        if let Some(ref autosave) = self.autosave {
+            if let Err(e) = autosave.save(&self.form) {
+                warn!("failed to autosave: {}", e);
+            }
+        }
+    }
+}
+
+/// An in-progress form saved to disk, keyed by the SHA-256 digest of the album URL.
+pub(super) struct Autosave {
+    path: PathBuf,
+}
+
+impl Autosave {
+    pub fn new<P>(dir: P, url: &str) -> Autosave
+    where
+        P: AsRef<Path>,
+    {
+        let filename = format!("{:x}.json", Sha256::digest(url.as_bytes()));
+
+        Autosave {
+            path: dir.as_ref().join(filename),
+        }
+    }
+
+    /// Reads the saved form. It returns `None` if there is no autosave.
+    pub fn load(&self) -> io::Result<Option<AlbumInput>> {
+        match fs::read(&self.path) {
+            Ok(data) => serde_json::from_slice(&data)
+                .map(Some)
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e)),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    pub fn save(&self, form: &AlbumInput) -> io::Result<()> {
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+
+        let data =
+            serde_json::to_vec(form).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+
+        fs::write(&self.path, data)
+    }
+
+    pub fn remove(&self) -> io::Result<()> {
+        match fs::remove_file(&self.path) {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+            _ => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+
+    use uuid::Uuid;
+
+    use crate::models::{album, song, Name};
+
+    use super::*;
+
+    fn build_album_input() -> AlbumInput {
+        let song = song::Builder::new()
+            .set_position(1)
+            .set_duration(209)
+            .add_name(Name::new("Heart Attack", "en", true, true))
+            .build();
+
+        let album = album::Builder::new()
+            .set_kind(album::Kind::Single)
+            .set_country("KR")
+            .set_released_on("2017-12-28")
+            .set_url("https://www.melon.com/album/detail.htm?albumId=10123637")
+            .add_name(Name::new("Chuu", "en", true, true))
+            .add_song(song)
+            .build();
+
+        AlbumInput::from(&album)
+    }
+
+    #[test]
+    fn test_undo_and_redo() {
+        let mut session = Session::new(build_album_input(), None);
+        assert!(!session.undo());
+
+        let before = session.form.clone();
+        session.record(before);
+        assert!(!session.undo());
+
+        let before = session.form.clone();
+        session.form.kind = album::Kind::Ep;
+        session.record(before);
+
+        let before = session.form.clone();
+        session.form.songs.clear();
+        session.record(before);
+
+        assert!(session.undo());
+        assert_eq!(session.form.songs.len(), 1);
+        assert!(session.undo());
+        assert_eq!(session.form.kind, album::Kind::Single);
+        assert!(!session.undo());
+
+        assert!(session.redo());
+        assert_eq!(session.form.kind, album::Kind::Ep);
+
+        let before = session.form.clone();
+        session.form.country = String::from("JP");
+        session.record(before);
+        assert!(!session.redo());
+    }
+
+    #[test]
+    fn test_autosave() -> io::Result<()> {
+        let dir = env::temp_dir().join(format!("multimeta-{}", Uuid::new_v4().to_hyphenated_ref()));
+        let form = build_album_input();
+
+        let autosave = Autosave::new(&dir, &form.url);
+        assert!(autosave.load()?.is_none());
+
+        let mut session = Session::new(form.clone(), Some(Autosave::new(&dir, &form.url)));
+        session.form.country = String::from("JP");
+        session.record(form);
+
+        let saved = autosave.load()?.unwrap();
+        assert!(saved == session.form);
+
+        session.finish();
+        assert!(autosave.load()?.is_none());
+
+        fs::remove_dir_all(&dir)
+    }
+}
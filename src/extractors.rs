@@ -1,76 +1,509 @@
+mod candidate;
 pub mod melon;
 pub mod mora;
 pub mod up_front_works;
+mod warning;
 
-pub use self::{melon::MelonExtractor, mora::MoraExtractor, up_front_works::UpFrontWorksExtractor};
+pub use self::{
+    candidate::Candidate, melon::MelonExtractor, mora::MoraExtractor,
+    up_front_works::UpFrontWorksExtractor, warning::Warning,
+};
 
-use std::{error, fmt, io};
+use std::{cmp, error, fmt, io, str::FromStr, sync::Arc};
 
+use serde::{de::DeserializeOwned, Serialize};
 use url::Url;
 
-use crate::models::Album;
+use crate::{
+    models::Album,
+    util::http::{self, Client},
+};
 
 pub type Result<T> = std::result::Result<T, ExtractionError>;
 
+// The maximum number of characters of a document included in an error.
+const MAX_SNIPPET_LEN: usize = 80;
+// The number of bytes before the location of a JSON error included in its snippet.
+const SNIPPET_CONTEXT_LEN: usize = 20;
+
+// The most pages of a discography to request, in case a store keeps serving the same page.
+const MAX_DISCOGRAPHY_PAGES: usize = 100;
+
 #[derive(Debug)]
-pub enum ExtractionError {
+pub enum ErrorKind {
     Factory,
-    FetchRequest(Box<ureq::Error>),
+    FetchRequest(http::Error),
     FetchBody(io::Error),
     InvalidUrl(&'static str),
-    InvalidDocument,
+    /// The document could not be parsed. This includes the reason given by the parser.
+    InvalidDocument(String),
+    MissingDocument(&'static str),
     MissingField(&'static str),
     InvalidField(&'static str),
 }
 
-impl error::Error for ExtractionError {}
-
-impl fmt::Display for ExtractionError {
+impl fmt::Display for ErrorKind {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             Self::Factory => f.write_str("could not construct an extractor from the url"),
             Self::FetchRequest(e) => write!(f, "{}", e),
             Self::FetchBody(e) => write!(f, "{}", e),
             Self::InvalidUrl(key) => write!(f, "invalid url: missing {}", key),
-            Self::InvalidDocument => f.write_str("could not parse document"),
+            Self::InvalidDocument(reason) => write!(f, "could not parse document: {}", reason),
+            Self::MissingDocument(kind) => write!(f, "missing document: {}", kind),
             Self::MissingField(field) => write!(f, "missing field: {}", field),
             Self::InvalidField(field) => write!(f, "invalid field: {}", field),
         }
     }
 }
 
+/// An error from an extractor, with the context in which it occurred.
+///
+/// The path is the CSS selector of an HTML node or the path to a JSON value, and the snippet is
+/// the offending text of the document.
+#[derive(Debug)]
+pub struct ExtractionError {
+    kind: ErrorKind,
+    store: Option<Store>,
+    url: Option<String>,
+    path: Option<String>,
+    snippet: Option<String>,
+}
+
+impl ExtractionError {
+    pub fn new(kind: ErrorKind) -> ExtractionError {
+        ExtractionError {
+            kind,
+            store: None,
+            url: None,
+            path: None,
+            snippet: None,
+        }
+    }
+
+    pub fn invalid_document<R>(reason: R) -> ExtractionError
+    where
+        R: Into<String>,
+    {
+        ExtractionError::new(ErrorKind::InvalidDocument(reason.into()))
+    }
+
+    pub fn missing_field(field: &'static str) -> ExtractionError {
+        ExtractionError::new(ErrorKind::MissingField(field))
+    }
+
+    pub fn invalid_field(field: &'static str) -> ExtractionError {
+        ExtractionError::new(ErrorKind::InvalidField(field))
+    }
+
+    pub fn kind(&self) -> &ErrorKind {
+        &self.kind
+    }
+
+    pub fn store(&self) -> Option<Store> {
+        self.store
+    }
+
+    pub fn url(&self) -> Option<&str> {
+        self.url.as_deref()
+    }
+
+    /// Returns the status code of the response, if the store responded with an error.
+    pub fn status(&self) -> Option<u16> {
+        match self.kind {
+            ErrorKind::FetchRequest(http::Error::Status { code, .. }) => Some(code),
+            _ => None,
+        }
+    }
+
+    pub fn path(&self) -> Option<&str> {
+        self.path.as_deref()
+    }
+
+    pub fn snippet(&self) -> Option<&str> {
+        self.snippet.as_deref()
+    }
+
+    pub fn with_store(mut self, store: Store) -> ExtractionError {
+        self.store = Some(store);
+        self
+    }
+
+    pub fn with_url<U>(mut self, url: U) -> ExtractionError
+    where
+        U: Into<String>,
+    {
+        self.url = Some(url.into());
+        self
+    }
+
+    pub fn with_path<P>(mut self, path: P) -> ExtractionError
+    where
+        P: Into<String>,
+    {
+        self.path = Some(path.into());
+        self
+    }
+
+    /// Sets the offending text, which is trimmed and truncated.
+    pub fn with_snippet(mut self, snippet: &str) -> ExtractionError {
+        self.snippet = Some(truncate(snippet.trim(), MAX_SNIPPET_LEN));
+        self
+    }
+}
+
+impl From<ErrorKind> for ExtractionError {
+    fn from(kind: ErrorKind) -> ExtractionError {
+        ExtractionError::new(kind)
+    }
+}
+
+impl error::Error for ExtractionError {}
+
+impl fmt::Display for ExtractionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(store) = self.store {
+            write!(f, "{}: ", store)?;
+        }
+
+        write!(f, "{}", self.kind)
+    }
+}
+
 pub trait Extractor {
-    fn extract(&self) -> self::Result<Album>;
+    fn extract(&self) -> self::Result<Extraction>;
 }
 
-pub fn factory(url: &Url) -> self::Result<Box<dyn Extractor>> {
+/// An extracted album and the warnings found while extracting it.
+#[derive(Serialize)]
+pub struct Extraction {
+    pub album: Album,
+    pub warnings: Vec<Warning>,
+}
+
+impl Extraction {
+    /// Creates an extraction, adding warnings that apply to all stores, e.g., a missing artwork
+    /// URL.
+    pub fn new(album: Album, mut warnings: Vec<Warning>) -> Extraction {
+        if album.artwork_url.is_none() {
+            warnings.push(Warning::MissingArtwork);
+        }
+
+        Extraction { album, warnings }
+    }
+}
+
+/// Options for extractors built by the factory.
+///
+/// Extractors ignore the options they do not support.
+#[derive(Clone, Debug, Default)]
+pub struct Options {
+    pub lyrics: bool,
+}
+
+pub fn factory(
+    url: &Url,
+    client: Arc<dyn Client>,
+    options: &Options,
+) -> self::Result<Box<dyn Extractor>> {
     if MelonExtractor::matches(&url) {
-        Ok(Box::new(MelonExtractor::from_url(&url)?))
+        let extractor = MelonExtractor::from_url(&url, client)?.with_lyrics(options.lyrics);
+        Ok(Box::new(extractor))
     } else if MoraExtractor::matches(&url) {
-        Ok(Box::new(MoraExtractor::from_url(&url)?))
+        Ok(Box::new(MoraExtractor::from_url(&url, client)?))
     } else if UpFrontWorksExtractor::matches(&url) {
-        Ok(Box::new(UpFrontWorksExtractor::from_url(&url)?))
+        Ok(Box::new(UpFrontWorksExtractor::from_url(&url, client)?))
+    } else {
+        Err(ErrorKind::Factory.into())
+    }
+}
+
+/// A store supported by an extractor.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Store {
+    Melon,
+    Mora,
+    UpFrontWorks,
+}
+
+impl Store {
+    pub const NAMES: [&'static str; 3] = ["melon", "mora", "up-front-works"];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Store::Melon => Self::NAMES[0],
+            Store::Mora => Self::NAMES[1],
+            Store::UpFrontWorks => Self::NAMES[2],
+        }
+    }
+}
+
+impl fmt::Display for Store {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for Store {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "melon" => Ok(Store::Melon),
+            "mora" => Ok(Store::Mora),
+            "up-front-works" => Ok(Store::UpFrontWorks),
+            _ => Err(format!("invalid store: {}", s)),
+        }
+    }
+}
+
+/// Saved store documents, e.g., pages saved from a browser.
+///
+/// The album ID is used to build the album URL. Stores that include it in their documents do not
+/// require it.
+#[derive(Debug, Default)]
+pub struct Documents {
+    pub album_id: Option<String>,
+    pub html: Option<String>,
+    pub json: Option<String>,
+}
+
+/// Parses saved documents of a store without making any requests.
+pub fn parse(store: Store, documents: &Documents) -> self::Result<Extraction> {
+    let result = match store {
+        Store::Melon => melon::parse_documents(documents),
+        Store::Mora => mora::parse_documents(documents),
+        Store::UpFrontWorks => up_front_works::parse_documents(documents),
+    };
+
+    result.map_err(|e| e.with_store(store))
+}
+
+/// Searches the albums of a store by artist or title.
+pub fn search(store: Store, client: &dyn Client, query: &str) -> self::Result<Vec<Candidate>> {
+    let result = match store {
+        Store::Melon => melon::search(client, query),
+        Store::Mora => mora::search(client, query),
+        Store::UpFrontWorks => up_front_works::search(client, query),
+    };
+
+    result.map_err(|e| e.with_store(store))
+}
+
+/// Returns the store of an artist page, if the URL is one.
+pub fn artist_store(url: &Url) -> Option<Store> {
+    if melon::is_artist_url(url) {
+        Some(Store::Melon)
+    } else if mora::is_artist_url(url) {
+        Some(Store::Mora)
+    } else if up_front_works::is_artist_url(url) {
+        Some(Store::UpFrontWorks)
     } else {
-        Err(ExtractionError::Factory)
+        None
+    }
+}
+
+/// Lists all albums on an artist page.
+pub fn discography(url: &Url, client: &dyn Client) -> self::Result<Vec<Candidate>> {
+    let store = artist_store(url).ok_or_else(|| ExtractionError::new(ErrorKind::Factory))?;
+
+    let result = match store {
+        Store::Melon => melon::discography(client, url),
+        Store::Mora => mora::discography(client, url),
+        Store::UpFrontWorks => up_front_works::discography(client, url),
+    };
+
+    result.map_err(|e| e.with_store(store))
+}
+
+fn fetch(client: &dyn Client, url: &str) -> self::Result<String> {
+    client
+        .get(url)
+        .map_err(ErrorKind::FetchRequest)
+        .and_then(|r| r.into_string().map_err(ErrorKind::FetchBody))
+        .map_err(|kind| ExtractionError::new(kind).with_url(url))
+}
+
+/// Deserializes a JSON document.
+///
+/// On failure, the error includes the path to the offending value and a snippet of the document
+/// at the location of the error.
+fn from_json<T>(json: &str) -> self::Result<T>
+where
+    T: DeserializeOwned,
+{
+    let mut deserializer = serde_json::Deserializer::from_str(json);
+
+    serde_path_to_error::deserialize(&mut deserializer).map_err(|e| {
+        let path = e.path().to_string();
+        let e = e.into_inner();
+
+        let snippet = json
+            .lines()
+            .nth(e.line().saturating_sub(1))
+            .map(|line| {
+                // The error is typically reported at the end of the offending value.
+                let mut start = cmp::min(
+                    e.column().saturating_sub(SNIPPET_CONTEXT_LEN + 1),
+                    line.len(),
+                );
+
+                while !line.is_char_boundary(start) {
+                    start -= 1;
+                }
+
+                &line[start..]
+            })
+            .unwrap_or_default();
+
+        let mut error = ExtractionError::invalid_document(e.to_string()).with_snippet(snippet);
+
+        if path != "." {
+            error = error.with_path(path);
+        }
+
+        error
+    })
+}
+
+fn truncate(s: &str, max_len: usize) -> String {
+    match s.char_indices().nth(max_len) {
+        Some((i, _)) => format!("{}…", &s[..i]),
+        None => s.into(),
     }
 }
 
 #[cfg(test)]
 mod tests {
+    use std::fs;
+
+    use crate::util::http::MemoryClient;
+
     use super::*;
 
+    #[test]
+    fn test_store_from_str() {
+        assert_eq!("melon".parse(), Ok(Store::Melon));
+        assert_eq!("mora".parse(), Ok(Store::Mora));
+        assert_eq!("up-front-works".parse(), Ok(Store::UpFrontWorks));
+        assert!("google".parse::<Store>().is_err());
+    }
+
+    #[test]
+    fn test_parse() {
+        let documents = Documents {
+            html: Some(fs::read_to_string("tests/fixtures/melon-10123637.html").unwrap()),
+            json: Some(fs::read_to_string("tests/fixtures/melon-10123637.json").unwrap()),
+            ..Documents::default()
+        };
+
+        let album = parse(Store::Melon, &documents).unwrap().album;
+        assert_eq!(
+            album.url,
+            "https://www.melon.com/album/detail.htm?albumId=10123637"
+        );
+
+        let documents = Documents {
+            json: Some(
+                fs::read_to_string("tests/fixtures/mora-43000001-4547366347050.json").unwrap(),
+            ),
+            ..Documents::default()
+        };
+
+        let album = parse(Store::Mora, &documents).unwrap().album;
+        assert_eq!(album.url, "https://mora.jp/package/43000001/4547366347050/");
+
+        let mut documents = Documents {
+            html: Some(fs::read_to_string("tests/fixtures/up-front-works-epce-7387.html").unwrap()),
+            ..Documents::default()
+        };
+
+        assert!(parse(Store::UpFrontWorks, &documents).is_err());
+
+        documents.album_id = Some(String::from("EPCE-7387"));
+        assert!(parse(Store::UpFrontWorks, &documents).is_ok());
+
+        assert!(parse(Store::Melon, &Documents::default()).is_err());
+    }
+
+    #[test]
+    fn test_extraction_error() {
+        let e = ExtractionError::new(ErrorKind::FetchRequest(http::Error::Status {
+            code: 503,
+            retry_after: None,
+        }))
+        .with_store(Store::Mora)
+        .with_url("https://mora.jp/package/43000001/4547366347050/");
+
+        assert_eq!(e.status(), Some(503));
+        assert_eq!(e.to_string(), "mora: request failed with status 503");
+
+        let e = ExtractionError::missing_field("album kind");
+        assert!(e.status().is_none());
+        assert_eq!(e.to_string(), "missing field: album kind");
+    }
+
+    #[test]
+    fn test_from_json() {
+        #[derive(Debug, serde::Deserialize)]
+        struct Root {
+            #[allow(dead_code)]
+            songs: Vec<Song>,
+        }
+
+        #[derive(Debug, serde::Deserialize)]
+        struct Song {
+            #[allow(dead_code)]
+            position: i32,
+        }
+
+        assert!(from_json::<Root>(r#"{"songs":[{"position":1}]}"#).is_ok());
+
+        let e = from_json::<Root>(r#"{"songs":[{"position":1},{"position":"2"}]}"#).unwrap_err();
+        assert_eq!(e.path(), Some("songs[1].position"));
+        assert_eq!(e.snippet(), Some(r#"n":1},{"position":"2"}]}"#));
+
+        let e = from_json::<Root>("<html />").unwrap_err();
+        assert!(e.path().is_none());
+        assert_eq!(e.snippet(), Some("<html />"));
+    }
+
+    #[test]
+    fn test_truncate() {
+        assert_eq!(truncate("melon", 8), "melon");
+        assert_eq!(truncate("모닝커피", 2), "모닝…");
+    }
+
     #[test]
     fn test_factory() {
+        let client: Arc<dyn Client> = Arc::new(MemoryClient::new());
+
         let url = Url::parse("http://www.melon.com/album/detail.htm?albumId=10141232").unwrap();
-        assert!(factory(&url).is_ok());
+        assert!(factory(&url, client.clone(), &Options::default()).is_ok());
 
         let url = Url::parse("http://mora.jp/package/43000001/4547366347050/").unwrap();
-        assert!(factory(&url).is_ok());
+        assert!(factory(&url, client.clone(), &Options::default()).is_ok());
 
         let url = Url::parse("http://www.up-front-works.jp/release/detail/EPCE-7387/").unwrap();
-        assert!(factory(&url).is_ok());
+        assert!(factory(&url, client.clone(), &Options::default()).is_ok());
 
         let url = Url::parse("http://www.google.com/").unwrap();
-        assert!(factory(&url).is_err());
+        assert!(factory(&url, client.clone(), &Options::default()).is_err());
+    }
+
+    #[test]
+    fn test_artist_store() {
+        let url = Url::parse("https://www.melon.com/artist/album.htm?artistId=986155").unwrap();
+        assert_eq!(artist_store(&url), Some(Store::Melon));
+
+        let url = Url::parse("https://mora.jp/artist/12345/").unwrap();
+        assert_eq!(artist_store(&url), Some(Store::Mora));
+
+        let url = Url::parse("http://www.up-front-works.jp/release/search/?artist=12").unwrap();
+        assert_eq!(artist_store(&url), Some(Store::UpFrontWorks));
+
+        let url = Url::parse("http://www.melon.com/album/detail.htm?albumId=10141232").unwrap();
+        assert!(artist_store(&url).is_none());
     }
 }
@@ -0,0 +1,92 @@
+use std::fmt;
+
+use serde::Serialize;
+
+use crate::{extractors::Store, models::album};
+
+/// An album listed by a store, e.g., in search results or an artist's releases.
+///
+/// Stores do not list every field, so only the store, URL, and name are required.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct Candidate {
+    pub store: Store,
+    pub url: String,
+    pub name: String,
+    pub artist_name: Option<String>,
+    pub released_on: Option<String>,
+    pub kind: Option<album::Kind>,
+    pub track_count: Option<usize>,
+}
+
+impl Candidate {
+    pub fn new<U, N>(store: Store, url: U, name: N) -> Candidate
+    where
+        U: Into<String>,
+        N: Into<String>,
+    {
+        Candidate {
+            store,
+            url: url.into(),
+            name: name.into(),
+            artist_name: None,
+            released_on: None,
+            kind: None,
+            track_count: None,
+        }
+    }
+}
+
+impl fmt::Display for Candidate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name)?;
+
+        if let Some(ref artist_name) = self.artist_name {
+            write!(f, " / {}", artist_name)?;
+        }
+
+        write!(
+            f,
+            " ({}, {}, ",
+            self.store,
+            self.released_on.as_deref().unwrap_or("?")
+        )?;
+
+        match self.kind {
+            Some(kind) => write!(f, "{}, ", kind)?,
+            None => f.write_str("?, ")?,
+        }
+
+        match self.track_count {
+            Some(1) => f.write_str("1 track)"),
+            Some(n) => write!(f, "{} tracks)", n),
+            None => f.write_str("? tracks)"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fmt() {
+        let mut candidate = Candidate::new(
+            Store::Melon,
+            "https://www.melon.com/album/detail.htm?albumId=10123637",
+            "Chuu",
+        );
+
+        assert_eq!(candidate.to_string(), "Chuu (melon, ?, ?, ? tracks)");
+
+        candidate.artist_name = Some(String::from("이달의 소녀 (츄)"));
+        candidate.released_on = Some(String::from("2017-12-28"));
+        candidate.kind = Some(album::Kind::Single);
+        candidate.track_count = Some(2);
+
+        assert_eq!(
+            candidate.to_string(),
+            "Chuu / 이달의 소녀 (츄) (melon, 2017-12-28, single, 2 tracks)"
+        );
+    }
+}
@@ -1,23 +1,44 @@
+use std::sync::Arc;
+
 use chrono::NaiveDate;
-use log::warn;
-use select::{document::Document, predicate::Class};
+use log::info;
+use once_cell::sync::Lazy;
+use regex::Regex;
+use select::{
+    document::Document,
+    node::Node,
+    predicate::{self, Attr, Class, Descendant},
+};
 use serde::Deserialize;
 use url::Url;
 
 use crate::{
-    extractors::{self, ExtractionError, Extractor},
-    models::{album, song, Album, Name},
+    extractors::{
+        self, fetch, from_json, Candidate, Documents, ErrorKind, Extraction, ExtractionError,
+        Extractor, Store, Warning,
+    },
+    models::{album, song, Album, Lyrics, Name},
+    util::{http::Client, script::detect_locale},
 };
 
 static HOST: &str = "www.melon.com";
 static HTML_ENDPOINT: &str = "https://www.melon.com/album/detail.htm";
 static JSON_ENDPOINT: &str = "https://www.melon.com/webplayer/getContsInfo.json";
+static SONG_ENDPOINT: &str = "https://www.melon.com/song/detail.htm";
+static SEARCH_ENDPOINT: &str = "https://www.melon.com/search/album/index.htm";
+static ARTIST_ALBUMS_ENDPOINT: &str = "https://www.melon.com/artist/albumPaging.htm";
+
+// The number of albums requested per page of an artist's albums.
+const ARTIST_ALBUMS_PAGE_SIZE: usize = 50;
 
 static COUNTRY: &str = "KR";
-static LOCALE: &str = "ko";
+// Titles with only Han characters are assumed to be Chinese.
+static HAN_LOCALE: &str = "zh";
 
 pub struct MelonExtractor {
     album_id: String,
+    client: Arc<dyn Client>,
+    lyrics: bool,
 }
 
 impl MelonExtractor {
@@ -25,97 +46,193 @@ impl MelonExtractor {
         url.host_str().map(|h| h == HOST).unwrap_or(false)
     }
 
-    pub fn from_url(url: &Url) -> extractors::Result<MelonExtractor> {
-        parse_album_id(url).map(MelonExtractor::new)
+    pub fn from_url(url: &Url, client: Arc<dyn Client>) -> extractors::Result<MelonExtractor> {
+        parse_album_id(url).map(|id| MelonExtractor::new(id, client))
     }
 
-    pub fn new<I>(album_id: I) -> MelonExtractor
+    pub fn new<I>(album_id: I, client: Arc<dyn Client>) -> MelonExtractor
     where
         I: Into<String>,
     {
         MelonExtractor {
             album_id: album_id.into(),
+            client,
+            lyrics: false,
         }
     }
 
+    /// Sets whether to fetch the lyrics of each song that has them.
+    ///
+    /// This makes an additional request per song.
+    pub fn with_lyrics(mut self, lyrics: bool) -> MelonExtractor {
+        self.lyrics = lyrics;
+        self
+    }
+
     fn fetch_html(&self) -> extractors::Result<String> {
-        let params = [("albumId", &self.album_id)];
-        let url = Url::parse_with_params(HTML_ENDPOINT, &params).unwrap();
-        fetch(&url.into_string())
+        fetch(self.client.as_ref(), &build_html_url(&self.album_id))
     }
 
     fn fetch_json(&self) -> extractors::Result<String> {
-        let params = [("contsType", "A"), ("contsIds", &self.album_id)];
-        let url = Url::parse_with_params(JSON_ENDPOINT, &params).unwrap();
-        fetch(&url.into_string())
+        fetch(self.client.as_ref(), &build_json_url(&self.album_id))
     }
-}
 
-impl Extractor for MelonExtractor {
-    fn extract(&self) -> extractors::Result<Album> {
+    fn fetch_song_html(&self, song_id: &str) -> extractors::Result<String> {
+        let params = [("songId", song_id)];
+        let url = Url::parse_with_params(SONG_ENDPOINT, &params).unwrap();
+        fetch(self.client.as_ref(), &url.into_string())
+    }
+
+    fn fetch_lyrics(
+        &self,
+        album: &mut Album,
+        json: &str,
+        warnings: &mut Vec<Warning>,
+    ) -> extractors::Result<()> {
+        let song_ids = parse_lyrics_song_ids(json)?;
+
+        for (position, song_id) in song_ids {
+            // Lyrics are matched by track number rather than by index, since the album may not
+            // list every track of the JSON document.
+            let song = match album.songs.iter_mut().find(|s| s.position == position) {
+                Some(song) => song,
+                None => continue,
+            };
+
+            info!("fetching lyrics for song {}", song_id);
+
+            // Lyrics are supplementary, so a failure to fetch or parse them is not fatal.
+            match self
+                .fetch_song_html(&song_id)
+                .and_then(|html| parse_lyrics(&html))
+            {
+                Ok(lyrics) => song.lyrics = Some(lyrics),
+                Err(e) => warnings.push(Warning::MissingLyrics {
+                    position: song.position,
+                    reason: e.to_string(),
+                }),
+            }
+        }
+
+        Ok(())
+    }
+
+    fn extract_album(&self) -> extractors::Result<Extraction> {
         let html = self.fetch_html()?;
         let json = self.fetch_json()?;
-        parse(&self.album_id, &html, &json)
+
+        let mut extraction = parse(&self.album_id, &html, &json)?;
+
+        if self.lyrics {
+            self.fetch_lyrics(&mut extraction.album, &json, &mut extraction.warnings)?;
+        }
+
+        Ok(extraction)
+    }
+}
+
+impl Extractor for MelonExtractor {
+    fn extract(&self) -> extractors::Result<Extraction> {
+        self.extract_album().map_err(|e| e.with_store(Store::Melon))
     }
 }
 
-fn fetch(url: &str) -> extractors::Result<String> {
-    ureq::get(url)
-        .call()
-        .map_err(|e| ExtractionError::FetchRequest(Box::new(e)))
-        .and_then(|r| r.into_string().map_err(ExtractionError::FetchBody))
+pub fn parse_documents(documents: &Documents) -> extractors::Result<Extraction> {
+    let html = documents
+        .html
+        .as_ref()
+        .ok_or(ErrorKind::MissingDocument("html"))?;
+    let json = documents
+        .json
+        .as_ref()
+        .ok_or(ErrorKind::MissingDocument("json"))?;
+
+    let album_id = match documents.album_id {
+        Some(ref id) => id.clone(),
+        None => parse_json_album_id(json)?,
+    };
+
+    parse(&album_id, html, json)
 }
 
-fn parse(album_id: &str, html: &str, json: &str) -> extractors::Result<Album> {
+fn parse(album_id: &str, html: &str, json: &str) -> extractors::Result<Extraction> {
+    let mut warnings = Vec::new();
+
     let builder = album::Builder::new();
 
     let builder = builder
         .set_country(COUNTRY)
         .set_url(&format!("{}?albumId={}", HTML_ENDPOINT, album_id));
 
-    let builder = parse_html(html, builder)?;
-    let builder = parse_json(json, builder)?;
+    let builder = parse_html(html, builder, &mut warnings)
+        .map_err(|e| e.with_url(build_html_url(album_id)))?;
+    let builder = parse_json(json, builder, &mut warnings)
+        .map_err(|e| e.with_url(build_json_url(album_id)))?;
 
-    Ok(builder.build())
+    Ok(Extraction::new(builder.build(), warnings))
 }
 
-fn parse_html(html: &str, builder: album::Builder) -> extractors::Result<album::Builder> {
+fn build_html_url(album_id: &str) -> String {
+    let params = [("albumId", album_id)];
+    let url = Url::parse_with_params(HTML_ENDPOINT, &params).unwrap();
+    url.into_string()
+}
+
+fn build_json_url(album_id: &str) -> String {
+    let params = [("contsType", "A"), ("contsIds", album_id)];
+    let url = Url::parse_with_params(JSON_ENDPOINT, &params).unwrap();
+    url.into_string()
+}
+
+fn parse_html(
+    html: &str,
+    builder: album::Builder,
+    warnings: &mut Vec<Warning>,
+) -> extractors::Result<album::Builder> {
     let document = Document::from(html);
 
     let mut node = document.find(Class("gubun"));
 
     let raw_kind = node
         .next()
-        .ok_or(ExtractionError::MissingField("album kind"))
+        .ok_or_else(|| ExtractionError::missing_field("album kind").with_path(".gubun"))
         .map(|n| n.text())?;
     let raw_kind = raw_kind.trim();
     // Remove surrounding brackets from text.
     let raw_kind = &raw_kind[1..raw_kind.len() - 1];
-    let kind = parse_album_kind(raw_kind)?;
+    let kind = parse_album_kind(raw_kind, warnings).map_err(|e| e.with_path(".gubun"))?;
 
     let builder = builder.set_kind(kind);
 
     Ok(builder)
 }
 
-fn parse_json(json: &str, builder: album::Builder) -> extractors::Result<album::Builder> {
-    let root: Root = serde_json::from_str(json).map_err(|_| ExtractionError::InvalidDocument)?;
+fn parse_json(
+    json: &str,
+    builder: album::Builder,
+    warnings: &mut Vec<Warning>,
+) -> extractors::Result<album::Builder> {
+    let root: Root = from_json(json)?;
 
     let songs = root.conts_list;
 
     let builder = if let Some(song) = songs.first() {
-        let raw_name = normalize_name(&song.album_name_web_list);
-        let name = Name::new(raw_name, LOCALE, true, true);
+        let raw_name = normalize_name(&song.album_name_web_list, warnings);
+        let locale = detect_locale(&raw_name, HAN_LOCALE);
+        let name = Name::new(raw_name.as_str(), locale, true, true);
+
+        let released_on = parse_release_date(&song.issue_date)
+            .map_err(|e| e.with_path("contsList[0].issueDate"))?;
 
         builder
-            .set_released_on(&parse_release_date(&song.issue_date)?)
+            .set_released_on(&released_on)
             .set_artwork_url(&parse_artwork_url(&song.album_img_path))
             .add_name(name)
     } else {
-        return Err(ExtractionError::MissingField("songs"));
+        return Err(ExtractionError::missing_field("songs").with_path("contsList"));
     };
 
-    let builder = parse_songs(&songs, builder)?;
+    let builder = parse_songs(&songs, builder, warnings)?;
 
     Ok(builder)
 }
@@ -123,12 +240,15 @@ fn parse_json(json: &str, builder: album::Builder) -> extractors::Result<album::
 fn parse_songs(
     songs: &[RawSong],
     mut builder: album::Builder,
+    warnings: &mut Vec<Warning>,
 ) -> extractors::Result<album::Builder> {
-    for song in songs {
-        let raw_name = normalize_name(&song.song_name);
-        let name = Name::new(raw_name, LOCALE, true, true);
+    for (i, song) in songs.iter().enumerate() {
+        let raw_name = normalize_name(&song.song_name, warnings);
+        let locale = detect_locale(&raw_name, HAN_LOCALE);
+        let name = Name::new(raw_name.as_str(), locale, true, true);
 
-        let position = parse_position(&song.track_no)?;
+        let position = parse_position(&song.track_no)
+            .map_err(|e| e.with_path(format!("contsList[{}].trackNo", i)))?;
         let duration = song.play_time;
 
         let song = song::Builder::new()
@@ -143,40 +263,246 @@ fn parse_songs(
     Ok(builder)
 }
 
-fn parse_album_id(url: &Url) -> extractors::Result<String> {
-    url.query_pairs()
-        .find(|&(ref k, _)| k == "albumId")
-        .map(|(_, v)| v.into_owned())
-        .ok_or(ExtractionError::InvalidUrl("albumId"))
+fn parse_json_album_id(json: &str) -> extractors::Result<String> {
+    let root: Root = from_json(json)?;
+
+    root.conts_list
+        .first()
+        .map(|song| song.album_id.clone())
+        .ok_or_else(|| ExtractionError::missing_field("album ID").with_path("contsList"))
 }
 
-fn parse_album_kind(s: &str) -> extractors::Result<album::Kind> {
-    match s {
-        "싱글" => Ok(album::Kind::Single),
-        "OST" => {
-            // "OST" is not guaranteed, but is very likely, to be a single.
-            warn!("assuming album kind 'OST' as 'single'");
-            Ok(album::Kind::Single)
+/// Returns the track numbers and song IDs of tracks that have lyrics, in track order.
+fn parse_lyrics_song_ids(json: &str) -> extractors::Result<Vec<(i32, String)>> {
+    let root: Root = from_json(json)?;
+
+    let mut song_ids = Vec::new();
+
+    for (i, song) in root.conts_list.iter().enumerate() {
+        if !song.has_web_lyrics {
+            continue;
         }
-        "리믹스" => {
-            warn!("assuming album kind '리믹스' as 'single'");
-            Ok(album::Kind::Single)
+
+        let position = parse_position(&song.track_no)
+            .map_err(|e| e.with_path(format!("contsList[{}].trackNo", i)))?;
+
+        song_ids.push((position, song.song_id.to_string()));
+    }
+
+    Ok(song_ids)
+}
+
+fn parse_lyrics(html: &str) -> extractors::Result<Lyrics> {
+    let document = Document::from(html);
+
+    let node = document
+        .find(Attr("id", "d_video_summary"))
+        .next()
+        .ok_or_else(|| ExtractionError::missing_field("lyrics").with_path("#d_video_summary"))?;
+
+    let mut raw_text = String::new();
+    collect_lyrics_text(&node, &mut raw_text);
+
+    let text = raw_text
+        .lines()
+        .map(|line| line.trim())
+        .collect::<Vec<_>>()
+        .join("\n");
+    let text = text.trim();
+
+    if text.is_empty() {
+        return Err(ExtractionError::missing_field("lyrics").with_path("#d_video_summary"));
+    }
+
+    let locale = detect_locale(text, HAN_LOCALE);
+
+    Ok(Lyrics::new(text, locale))
+}
+
+// Lines are separated by `br` elements, and the text nodes include the whitespace of the document
+// indentation.
+fn collect_lyrics_text(node: &Node<'_>, buf: &mut String) {
+    for child in node.children() {
+        if let Some(text) = child.as_text() {
+            buf.push_str(text.trim_matches(|c| c == '\n' || c == '\r'));
+        } else if child.name() == Some("br") {
+            buf.push('\n');
+        } else if child.name().is_some() {
+            collect_lyrics_text(&child, buf);
         }
-        "EP" => Ok(album::Kind::Ep),
-        "정규" => Ok(album::Kind::Lp),
-        "옴니버스" => {
-            // "Omnibus" is probably either an EP or LP, but since it's
-            // typically a collection, assume it's an album.
-            warn!("assuming album kind '옴니버스' as 'LP'");
-            Ok(album::Kind::Lp)
+    }
+}
+
+/// Searches albums by artist or title.
+pub fn search(client: &dyn Client, query: &str) -> extractors::Result<Vec<Candidate>> {
+    let params = [("q", query), ("section", "album")];
+    let url = Url::parse_with_params(SEARCH_ENDPOINT, &params).unwrap();
+    let url = url.as_str();
+
+    let html = fetch(client, url)?;
+
+    parse_album_list(&html).map_err(|e| e.with_url(url))
+}
+
+/// Returns whether the URL is an artist page, e.g.,
+/// `https://www.melon.com/artist/album.htm?artistId=986155`.
+pub fn is_artist_url(url: &Url) -> bool {
+    url.host_str().map(|h| h == HOST).unwrap_or(false) && url.path().starts_with("/artist/")
+}
+
+/// Lists all albums of an artist, newest first.
+pub fn discography(client: &dyn Client, url: &Url) -> extractors::Result<Vec<Candidate>> {
+    let artist_id = parse_artist_id(url)?;
+    fetch_artist_albums(client, &artist_id, ARTIST_ALBUMS_PAGE_SIZE)
+}
+
+// Requests pages of an artist's albums until a page is short or adds no new albums, e.g., when
+// the store ignores the start index and serves the same page again.
+fn fetch_artist_albums(
+    client: &dyn Client,
+    artist_id: &str,
+    page_size: usize,
+) -> extractors::Result<Vec<Candidate>> {
+    let mut candidates: Vec<Candidate> = Vec::new();
+
+    for _ in 0..extractors::MAX_DISCOGRAPHY_PAGES {
+        let start_index = (candidates.len() + 1).to_string();
+        let page_size_param = page_size.to_string();
+
+        let params = [
+            ("startIndex", start_index.as_str()),
+            ("pageSize", page_size_param.as_str()),
+            ("listType", "0"),
+            ("orderBy", "ISSUE_DATE"),
+            ("artistId", artist_id),
+        ];
+
+        let url = Url::parse_with_params(ARTIST_ALBUMS_ENDPOINT, &params).unwrap();
+        let url = url.as_str();
+
+        let html = fetch(client, url)?;
+        let page = parse_album_list(&html).map_err(|e| e.with_url(url))?;
+        let mut is_last_page = page.len() < page_size;
+        let mut has_new_albums = false;
+
+        for candidate in page {
+            if !candidates.iter().any(|c| c.url == candidate.url) {
+                candidates.push(candidate);
+                has_new_albums = true;
+            }
         }
-        "베스트" => {
-            // "Best" is probably a compilation album.
-            warn!("assuming album kind '베스트' as 'LP'");
-            Ok(album::Kind::Lp)
+
+        is_last_page |= !has_new_albums;
+
+        if is_last_page {
+            break;
         }
-        _ => Err(ExtractionError::InvalidField("album kind")),
     }
+
+    Ok(candidates)
+}
+
+/// Parses a list of albums, e.g., search results or a page of an artist's albums.
+fn parse_album_list(html: &str) -> extractors::Result<Vec<Candidate>> {
+    static ALBUM_ID_RE: Lazy<Regex> =
+        Lazy::new(|| Regex::new(r"goAlbumDetail\('(\d+)'\)").unwrap());
+
+    let document = Document::from(html);
+    let mut candidates = Vec::new();
+
+    for node in document.find(Class("album11_li")) {
+        let link = node
+            .find(Descendant(predicate::Name("dt"), Class("ellipsis")))
+            .next()
+            .ok_or_else(|| {
+                ExtractionError::missing_field("name").with_path(".album11_li dt .ellipsis")
+            })?;
+
+        let album_id = link
+            .attr("href")
+            .and_then(|href| ALBUM_ID_RE.captures(href))
+            .map(|captures| captures[1].to_string())
+            .ok_or_else(|| {
+                ExtractionError::missing_field("album ID").with_path(".album11_li dt .ellipsis")
+            })?;
+
+        let name = normalize_name(link.text().trim(), &mut Vec::new());
+        let mut candidate = Candidate::new(Store::Melon, build_html_url(&album_id), name);
+
+        candidate.artist_name = node
+            .find(Descendant(Class("atistname"), predicate::Name("a")))
+            .next()
+            .map(|n| n.text().trim().to_string());
+
+        candidate.kind = node.find(Class("vdo_name")).next().and_then(|n| {
+            let raw_kind = n.text();
+            let raw_kind = raw_kind
+                .trim()
+                .trim_start_matches('[')
+                .trim_end_matches(']');
+            // The kind is only informational, so guesses are not reported.
+            parse_album_kind(raw_kind, &mut Vec::new()).ok()
+        });
+
+        candidate.released_on = node.find(Class("cnt_view")).next().and_then(|n| {
+            NaiveDate::parse_from_str(n.text().trim(), "%Y.%m.%d")
+                .map(|d| d.format("%F").to_string())
+                .ok()
+        });
+
+        candidate.track_count = node
+            .find(Class("tot_song"))
+            .next()
+            .and_then(|n| n.text().trim().trim_end_matches('곡').parse().ok());
+
+        candidates.push(candidate);
+    }
+
+    Ok(candidates)
+}
+
+fn parse_artist_id(url: &Url) -> extractors::Result<String> {
+    url.query_pairs()
+        .find(|(k, _)| k == "artistId")
+        .map(|(_, v)| v.into_owned())
+        .ok_or_else(|| {
+            ExtractionError::new(ErrorKind::InvalidUrl("artistId")).with_url(url.as_str())
+        })
+}
+
+fn parse_album_id(url: &Url) -> extractors::Result<String> {
+    url.query_pairs()
+        .find(|&(ref k, _)| k == "albumId")
+        .map(|(_, v)| v.into_owned())
+        .ok_or_else(|| {
+            ExtractionError::new(ErrorKind::InvalidUrl("albumId")).with_url(url.as_str())
+        })
+}
+
+fn parse_album_kind(s: &str, warnings: &mut Vec<Warning>) -> extractors::Result<album::Kind> {
+    let (kind, is_guess) = match s {
+        "싱글" => (album::Kind::Single, false),
+        // "OST" is not guaranteed, but is very likely, to be a single.
+        "OST" => (album::Kind::Single, true),
+        "리믹스" => (album::Kind::Single, true),
+        "EP" => (album::Kind::Ep, false),
+        "정규" => (album::Kind::Lp, false),
+        // "Omnibus" is probably either an EP or LP, but since it's
+        // typically a collection, assume it's an album.
+        "옴니버스" => (album::Kind::Lp, true),
+        // "Best" is probably a compilation album.
+        "베스트" => (album::Kind::Lp, true),
+        _ => return Err(ExtractionError::invalid_field("album kind").with_snippet(s)),
+    };
+
+    if is_guess {
+        warnings.push(Warning::GuessedAlbumKind {
+            kind,
+            reason: format!("store kind '{}'", s),
+        });
+    }
+
+    Ok(kind)
 }
 
 fn parse_artwork_url(s: &str) -> String {
@@ -187,23 +513,33 @@ fn parse_artwork_url(s: &str) -> String {
 
 fn parse_position(s: &str) -> extractors::Result<i32> {
     s.parse()
-        .map_err(|_| ExtractionError::InvalidField("position"))
+        .map_err(|_| ExtractionError::invalid_field("position").with_snippet(s))
 }
 
 fn parse_release_date(s: &str) -> extractors::Result<String> {
     NaiveDate::parse_from_str(s, "%Y%m%d")
         .map(|d| d.format("%F").to_string())
-        .map_err(|_| ExtractionError::InvalidField("release date"))
+        .map_err(|_| ExtractionError::invalid_field("release date").with_snippet(s))
 }
 
-fn normalize_name(name: &str) -> String {
-    name.replace("`", "'")
+fn normalize_name(name: &str, warnings: &mut Vec<Warning>) -> String {
+    let normalized = name
+        .replace("`", "'")
         .replace("‘", "'")
         .replace("’", "'")
         .replace("′", "'")
         .replace("&#34;", "\"")
         .replace("&#39;", "'")
-        .replace(" Of ", " of ")
+        .replace(" Of ", " of ");
+
+    if normalized != name {
+        warnings.push(Warning::NormalizedName {
+            original: name.into(),
+            normalized: normalized.clone(),
+        });
+    }
+
+    normalized
 }
 
 #[derive(Deserialize)]
@@ -215,10 +551,14 @@ struct Root {
 #[derive(Deserialize)]
 #[serde(rename_all = "camelCase")]
 struct RawSong {
+    album_id: String,
     album_img_path: String,
     album_name_web_list: String,
+    #[serde(default)]
+    has_web_lyrics: bool,
     issue_date: String,
     play_time: i32,
+    song_id: u64,
     song_name: String,
     track_no: String,
 }
@@ -227,6 +567,8 @@ struct RawSong {
 mod tests {
     use std::fs;
 
+    use crate::util::http::MemoryClient;
+
     use super::*;
 
     #[test]
@@ -249,7 +591,15 @@ mod tests {
         let html = fs::read_to_string("tests/fixtures/melon-10123637.html").unwrap();
         let json = fs::read_to_string("tests/fixtures/melon-10123637.json").unwrap();
 
-        let album = parse("10123637", &html, &json).unwrap();
+        let Extraction { album, warnings } = parse("10123637", &html, &json).unwrap();
+
+        assert_eq!(
+            warnings,
+            [Warning::NormalizedName {
+                original: String::from("Girl`s Talk (이브, 츄)"),
+                normalized: String::from("Girl's Talk (이브, 츄)"),
+            }]
+        );
 
         assert_eq!(album.kind, album::Kind::Single);
         assert_eq!(album.country, "KR");
@@ -266,7 +616,7 @@ mod tests {
         );
 
         assert_eq!(album.names.len(), 1);
-        assert_eq!(&album.names[0], &Name::new("Chuu", "ko", true, true));
+        assert_eq!(&album.names[0], &Name::new("Chuu", "en", true, true));
 
         assert_eq!(album.songs.len(), 2);
 
@@ -276,7 +626,7 @@ mod tests {
         assert_eq!(song.names.len(), 1);
         assert_eq!(
             &song.names[0],
-            &Name::new("Heart Attack (츄)", "ko", true, true)
+            &Name::new("Heart Attack (츄)", "en", true, true)
         );
 
         let song = &album.songs[1];
@@ -285,20 +635,253 @@ mod tests {
         assert_eq!(song.names.len(), 1);
         assert_eq!(
             &song.names[0],
-            &Name::new("Girl's Talk (이브, 츄)", "ko", true, true)
+            &Name::new("Girl's Talk (이브, 츄)", "en", true, true)
         );
     }
 
+    #[test]
+    fn test_extract() {
+        let client = MemoryClient::new();
+        client
+            .insert_file(
+                "https://www.melon.com/album/detail.htm?albumId=10123637",
+                "tests/fixtures/melon-10123637.html",
+            )
+            .unwrap();
+        client
+            .insert_file(
+                "https://www.melon.com/webplayer/getContsInfo.json?contsType=A&contsIds=10123637",
+                "tests/fixtures/melon-10123637.json",
+            )
+            .unwrap();
+        client
+            .insert_file(
+                "https://www.melon.com/song/detail.htm?songId=30809890",
+                "tests/fixtures/melon-song-detail.html",
+            )
+            .unwrap();
+
+        let extractor = MelonExtractor::new("10123637", Arc::new(client)).with_lyrics(true);
+        let Extraction { album, warnings } = extractor.extract().unwrap();
+
+        assert_eq!(album.names[0].name, "Chuu");
+        assert_eq!(album.songs.len(), 2);
+        assert!(album.songs[0].lyrics.is_some());
+        // The lyrics of the second song are not served, which is not a fatal error.
+        assert!(album.songs[1].lyrics.is_none());
+        assert!(warnings.contains(&Warning::MissingLyrics {
+            position: 2,
+            reason: String::from("request failed with status 404"),
+        }));
+    }
+
+    #[test]
+    fn test_fetch_lyrics_by_track_number() {
+        let client = MemoryClient::new();
+        client
+            .insert_file(
+                "https://www.melon.com/song/detail.htm?songId=30809890",
+                "tests/fixtures/melon-song-detail.html",
+            )
+            .unwrap();
+
+        let html = fs::read_to_string("tests/fixtures/melon-10123637.html").unwrap();
+        let json = fs::read_to_string("tests/fixtures/melon-10123637.json").unwrap();
+        let Extraction { mut album, .. } = parse("10123637", &html, &json).unwrap();
+
+        // The first track is missing, so the lyrics of the second song must not be taken from it.
+        album.songs.remove(0);
+
+        let extractor = MelonExtractor::new("10123637", Arc::new(client));
+        let mut warnings = Vec::new();
+        extractor
+            .fetch_lyrics(&mut album, &json, &mut warnings)
+            .unwrap();
+
+        assert!(album.songs[0].lyrics.is_none());
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings.contains(&Warning::MissingLyrics {
+            position: 2,
+            reason: String::from("request failed with status 404"),
+        }));
+    }
+
     #[test]
     fn test_parse_html_with_empty_document() {
         let builder = album::Builder::new();
-        assert!(parse_json("<html />", builder).is_err());
+        assert!(parse_json("<html />", builder, &mut Vec::new()).is_err());
+    }
+
+    #[test]
+    fn test_parse_with_invalid_album_kind() {
+        let html = r#"<span class="gubun">[데모]</span>"#;
+        let json = fs::read_to_string("tests/fixtures/melon-10123637.json").unwrap();
+
+        let e = parse("10123637", html, &json).err().unwrap();
+        assert_eq!(
+            e.url(),
+            Some("https://www.melon.com/album/detail.htm?albumId=10123637")
+        );
+        assert_eq!(e.path(), Some(".gubun"));
+        assert_eq!(e.snippet(), Some("데모"));
     }
 
     #[test]
     fn test_parse_json_with_empty_root() {
         let builder = album::Builder::new();
-        assert!(parse_json("{}", builder).is_err());
+        assert!(parse_json("{}", builder, &mut Vec::new()).is_err());
+    }
+
+    #[test]
+    fn test_parse_json_album_id() {
+        let json = fs::read_to_string("tests/fixtures/melon-10123637.json").unwrap();
+        assert_eq!(parse_json_album_id(&json).unwrap(), "10123637");
+
+        assert!(parse_json_album_id(r#"{"contsList":[]}"#).is_err());
+    }
+
+    #[test]
+    fn test_parse_lyrics_song_ids() {
+        let json = fs::read_to_string("tests/fixtures/melon-10123637.json").unwrap();
+        let song_ids = parse_lyrics_song_ids(&json).unwrap();
+
+        assert_eq!(
+            song_ids,
+            vec![(1, String::from("30809890")), (2, String::from("30809891"))]
+        );
+    }
+
+    #[test]
+    fn test_parse_lyrics() {
+        let html = fs::read_to_string("tests/fixtures/melon-song-detail.html").unwrap();
+        let lyrics = parse_lyrics(&html).unwrap();
+
+        assert_eq!(lyrics.text, "첫 번째 줄\nSecond line\n\n세 번째 줄");
+        assert_eq!(lyrics.locale, "ko");
+
+        assert!(parse_lyrics("<html />").is_err());
+    }
+
+    #[test]
+    fn test_search() {
+        let client = MemoryClient::new();
+        client
+            .insert_file(
+                "https://www.melon.com/search/album/index.htm?q=%EC%B8%84&section=album",
+                "tests/fixtures/melon-search.html",
+            )
+            .unwrap();
+
+        let candidates = search(&client, "츄").unwrap();
+        assert_eq!(candidates.len(), 2);
+
+        assert!(search(&MemoryClient::new(), "츄").is_err());
+    }
+
+    #[test]
+    fn test_parse_album_list() {
+        let html = fs::read_to_string("tests/fixtures/melon-search.html").unwrap();
+        let candidates = parse_album_list(&html).unwrap();
+
+        assert_eq!(candidates.len(), 2);
+
+        assert_eq!(
+            candidates[0],
+            Candidate {
+                store: Store::Melon,
+                url: String::from("https://www.melon.com/album/detail.htm?albumId=10123637"),
+                name: String::from("Chuu"),
+                artist_name: Some(String::from("이달의 소녀 (츄)")),
+                released_on: Some(String::from("2017-12-28")),
+                kind: Some(album::Kind::Single),
+                track_count: Some(2),
+            }
+        );
+
+        assert_eq!(candidates[1].kind, Some(album::Kind::Ep));
+        assert_eq!(candidates[1].track_count, Some(7));
+
+        assert!(parse_album_list("<html />").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_is_artist_url() {
+        let url = Url::parse("https://www.melon.com/artist/album.htm?artistId=986155").unwrap();
+        assert!(is_artist_url(&url));
+
+        let url = Url::parse("https://www.melon.com/album/detail.htm?albumId=10123637").unwrap();
+        assert!(!is_artist_url(&url));
+
+        let url = Url::parse("https://mora.jp/artist/1/").unwrap();
+        assert!(!is_artist_url(&url));
+    }
+
+    #[test]
+    fn test_discography() {
+        let client = MemoryClient::new();
+        client
+            .insert_file(
+                "https://www.melon.com/artist/albumPaging.htm?startIndex=1&pageSize=50&listType=0&orderBy=ISSUE_DATE&artistId=986155",
+                "tests/fixtures/melon-artist-albums-1.html",
+            )
+            .unwrap();
+
+        let url = Url::parse("https://www.melon.com/artist/album.htm?artistId=986155").unwrap();
+        let candidates = discography(&client, &url).unwrap();
+        assert_eq!(candidates.len(), 2);
+        assert_eq!(candidates[0].name, "Howl");
+        assert_eq!(candidates[0].track_count, Some(5));
+
+        let url = Url::parse("https://www.melon.com/artist/album.htm").unwrap();
+        assert!(discography(&client, &url).is_err());
+    }
+
+    #[test]
+    fn test_fetch_artist_albums_with_pages() {
+        let client = MemoryClient::new();
+        client
+            .insert_file(
+                "https://www.melon.com/artist/albumPaging.htm?startIndex=1&pageSize=2&listType=0&orderBy=ISSUE_DATE&artistId=986155",
+                "tests/fixtures/melon-artist-albums-1.html",
+            )
+            .unwrap();
+        client
+            .insert_file(
+                "https://www.melon.com/artist/albumPaging.htm?startIndex=3&pageSize=2&listType=0&orderBy=ISSUE_DATE&artistId=986155",
+                "tests/fixtures/melon-artist-albums-2.html",
+            )
+            .unwrap();
+
+        let candidates = fetch_artist_albums(&client, "986155", 2).unwrap();
+        let names: Vec<&str> = candidates.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(names, ["Howl", "Chuu", "Heart Attack"]);
+    }
+
+    #[test]
+    fn test_fetch_artist_albums_with_repeated_page() {
+        // The store ignores the start index and serves the first page again.
+        let client = MemoryClient::new();
+
+        for start_index in &["1", "3"] {
+            client
+                .insert_file(
+                    format!("https://www.melon.com/artist/albumPaging.htm?startIndex={}&pageSize=2&listType=0&orderBy=ISSUE_DATE&artistId=986155", start_index),
+                    "tests/fixtures/melon-artist-albums-1.html",
+                )
+                .unwrap();
+        }
+
+        let candidates = fetch_artist_albums(&client, "986155", 2).unwrap();
+        assert_eq!(candidates.len(), 2);
+    }
+
+    #[test]
+    fn test_parse_artist_id() {
+        let url = Url::parse("https://www.melon.com/artist/album.htm?artistId=986155").unwrap();
+        assert_eq!(parse_artist_id(&url).unwrap(), "986155");
+
+        let url = Url::parse("https://www.melon.com/artist/album.htm").unwrap();
+        assert!(parse_artist_id(&url).is_err());
     }
 
     #[test]
@@ -312,17 +895,51 @@ mod tests {
 
     #[test]
     fn test_parse_album_kind() {
-        assert_eq!(parse_album_kind("싱글").unwrap(), album::Kind::Single);
-        assert_eq!(parse_album_kind("EP").unwrap(), album::Kind::Ep);
-        assert_eq!(parse_album_kind("정규").unwrap(), album::Kind::Lp);
-        assert_eq!(parse_album_kind("OST").unwrap(), album::Kind::Single);
-        assert_eq!(parse_album_kind("리믹스").unwrap(), album::Kind::Single);
-        assert_eq!(parse_album_kind("옴니버스").unwrap(), album::Kind::Lp);
+        let mut warnings = Vec::new();
+
+        assert_eq!(
+            parse_album_kind("싱글", &mut warnings).unwrap(),
+            album::Kind::Single
+        );
+        assert_eq!(
+            parse_album_kind("EP", &mut warnings).unwrap(),
+            album::Kind::Ep
+        );
+        assert_eq!(
+            parse_album_kind("정규", &mut warnings).unwrap(),
+            album::Kind::Lp
+        );
+        assert!(warnings.is_empty());
+
+        assert_eq!(
+            parse_album_kind("OST", &mut warnings).unwrap(),
+            album::Kind::Single
+        );
+        assert_eq!(
+            parse_album_kind("리믹스", &mut warnings).unwrap(),
+            album::Kind::Single
+        );
+        assert_eq!(
+            parse_album_kind("옴니버스", &mut warnings).unwrap(),
+            album::Kind::Lp
+        );
 
         // https://www.melon.com/album/detail.htm?albumId=10404130
-        assert_eq!(parse_album_kind("베스트").unwrap(), album::Kind::Lp);
+        assert_eq!(
+            parse_album_kind("베스트", &mut warnings).unwrap(),
+            album::Kind::Lp
+        );
 
-        assert!(parse_album_kind("foo").is_err());
+        assert!(parse_album_kind("foo", &mut warnings).is_err());
+
+        assert_eq!(warnings.len(), 4);
+        assert_eq!(
+            warnings[0],
+            Warning::GuessedAlbumKind {
+                kind: album::Kind::Single,
+                reason: String::from("store kind 'OST'"),
+            }
+        );
     }
 
     #[test]
@@ -340,36 +957,53 @@ mod tests {
 
     #[test]
     fn test_normalize_name() {
+        let mut warnings = Vec::new();
+
         // https://www.melon.com/album/detail.htm?albumId=10123637
         assert_eq!(
-            normalize_name("Girl`s Talk (이브, 츄)"),
+            normalize_name("Girl`s Talk (이브, 츄)", &mut warnings),
             "Girl's Talk (이브, 츄)"
         );
 
         // https://www.melon.com/album/detail.htm?albumId=598055
-        assert_eq!(normalize_name("I Don’t Care"), "I Don't Care");
+        assert_eq!(
+            normalize_name("I Don’t Care", &mut warnings),
+            "I Don't Care"
+        );
 
         // https://www.melon.com/album/detail.htm?albumId=10288337
         assert_eq!(
-            normalize_name("3YE 1st Digital Single ‘DMT`"),
+            normalize_name("3YE 1st Digital Single ‘DMT`", &mut warnings),
             "3YE 1st Digital Single 'DMT'"
         );
 
         // https://www.melon.com/album/detail.htm?albumId=10353029
-        assert_eq!(normalize_name("Let′s Go Everywhere"), "Let's Go Everywhere");
+        assert_eq!(
+            normalize_name("Let′s Go Everywhere", &mut warnings),
+            "Let's Go Everywhere"
+        );
 
         // https://www.melon.com/album/detail.htm?albumId=10310489
         assert_eq!(
-            normalize_name("&#34;개 같은 하루 (with TTG)&#34; OST"),
+            normalize_name("&#34;개 같은 하루 (with TTG)&#34; OST", &mut warnings),
             r#""개 같은 하루 (with TTG)" OST"#,
         );
 
         // https://www.melon.com/album/detail.htm?albumId=10309095
-        assert_eq!(normalize_name("서핑해 (Surfin&#39;)"), "서핑해 (Surfin')");
+        assert_eq!(
+            normalize_name("서핑해 (Surfin&#39;)", &mut warnings),
+            "서핑해 (Surfin')"
+        );
 
         // https://www.melon.com/album/detail.htm?albumId=10074419
-        let actual = normalize_name("Love Don`t Hurt (Feat. Amber Of f(x))");
+        let actual = normalize_name("Love Don`t Hurt (Feat. Amber Of f(x))", &mut warnings);
         let expected = "Love Don't Hurt (Feat. Amber of f(x))";
         assert_eq!(actual, expected);
+
+        assert_eq!(warnings.len(), 7);
+
+        warnings.clear();
+        assert_eq!(normalize_name("Chuu", &mut warnings), "Chuu");
+        assert!(warnings.is_empty());
     }
 }
@@ -1,3 +1,5 @@
+use std::sync::Arc;
+
 use chrono::NaiveDate;
 use select::{
     document::Document,
@@ -7,23 +9,28 @@ use serde::Deserialize;
 use url::Url;
 
 use crate::{
-    extractors::{self, ExtractionError, Extractor},
-    models::{album, song, Album, Name},
+    extractors::{
+        self, fetch, from_json, Candidate, Documents, ErrorKind, Extraction, ExtractionError,
+        Extractor, Store, Warning,
+    },
+    models::{album, song, Name},
+    util::{http::Client, script::detect_locale},
 };
 
-static USER_AGENT: &str = concat!(env!("CARGO_PKG_NAME"), "/", env!("CARGO_PKG_VERSION"));
-
 static HOST: &str = "mora.jp";
 
 static HTML_BASE_URL: &str = "https://mora.jp/package";
 static JSON_BASE_URL: &str = "https://cf.mora.jp/contents/package";
 static JSON_FILENAME: &str = "packageMeta.json";
+static SEARCH_ENDPOINT: &str = "https://mora.jp/search/api/package";
 
 static COUNTRY: &str = "JP";
-static LOCALE: &str = "ja";
+// Titles with only Han characters are assumed to be Japanese.
+static HAN_LOCALE: &str = "ja";
 
 pub struct MoraExtractor {
     album_id: String,
+    client: Arc<dyn Client>,
 }
 
 impl MoraExtractor {
@@ -31,63 +38,81 @@ impl MoraExtractor {
         url.host_str().map(|h| h == HOST).unwrap_or(false)
     }
 
-    pub fn from_url(url: &Url) -> extractors::Result<MoraExtractor> {
-        parse_album_id(url).map(MoraExtractor::new)
+    pub fn from_url(url: &Url, client: Arc<dyn Client>) -> extractors::Result<MoraExtractor> {
+        parse_album_id(url).map(|id| MoraExtractor::new(id, client))
     }
 
-    pub fn new<I>(album_id: I) -> MoraExtractor
+    pub fn new<I>(album_id: I, client: Arc<dyn Client>) -> MoraExtractor
     where
         I: Into<String>,
     {
         MoraExtractor {
             album_id: album_id.into(),
+            client,
         }
     }
 
     fn fetch_html(&self) -> extractors::Result<String> {
-        let url = format!("{}/{}/", HTML_BASE_URL, self.album_id);
-        fetch(&url)
+        fetch(self.client.as_ref(), &build_html_url(&self.album_id))
     }
-}
 
-impl Extractor for MoraExtractor {
-    fn extract(&self) -> extractors::Result<Album> {
+    fn extract_album(&self) -> extractors::Result<Extraction> {
         let html = self.fetch_html()?;
 
-        let arguments = parse_html(&html)?;
+        let arguments =
+            parse_html(&html).map_err(|e| e.with_url(build_html_url(&self.album_id)))?;
         let json_endpoint = build_json_endpoint(
             &arguments.mount_point,
             &arguments.label_id,
             &arguments.material_no,
         );
 
-        let json = fetch(&json_endpoint)?;
+        let json = fetch(self.client.as_ref(), &json_endpoint)?;
+
+        parse(&self.album_id, &json).map_err(|e| e.with_url(json_endpoint))
+    }
+}
 
-        parse(&self.album_id, &json)
+impl Extractor for MoraExtractor {
+    fn extract(&self) -> extractors::Result<Extraction> {
+        self.extract_album().map_err(|e| e.with_store(Store::Mora))
     }
 }
 
-fn fetch(url: &str) -> extractors::Result<String> {
-    ureq::get(url)
-        // A user agent is required to make a request to mora.jp.
-        .set("User-Agent", USER_AGENT)
-        .call()
-        .map_err(|e| ExtractionError::FetchRequest(Box::new(e)))
-        .and_then(|r| r.into_string().map_err(ExtractionError::FetchBody))
+pub fn parse_documents(documents: &Documents) -> extractors::Result<Extraction> {
+    let json = documents
+        .json
+        .as_ref()
+        .ok_or(ErrorKind::MissingDocument("json"))?;
+
+    let album_id = match documents.album_id {
+        Some(ref id) => id.clone(),
+        None => parse_json_album_id(json)?,
+    };
+
+    parse(&album_id, json)
 }
 
-fn parse(album_id: &str, json: &str) -> extractors::Result<Album> {
+fn parse(album_id: &str, json: &str) -> extractors::Result<Extraction> {
+    let mut warnings = Vec::new();
+
     let builder = album::Builder::new()
         .set_country(COUNTRY)
-        .set_url(&format!("{}/{}/", HTML_BASE_URL, album_id));
+        .set_url(&build_html_url(album_id));
 
-    let builder = parse_json(json, builder)?;
+    let builder = parse_json(json, builder, &mut warnings)?;
 
-    Ok(builder.build())
+    Ok(Extraction::new(builder.build(), warnings))
+}
+
+fn build_html_url(album_id: &str) -> String {
+    format!("{}/{}/", HTML_BASE_URL, album_id)
 }
 
 fn parse_html(html: &str) -> extractors::Result<Arguments> {
-    Document::from(html)
+    static SELECTOR: &str = "meta[name=msApplication-Arguments]";
+
+    let data = Document::from(html)
         .find(And(
             predicate::Name("meta"),
             Attr("name", "msApplication-Arguments"),
@@ -95,21 +120,36 @@ fn parse_html(html: &str) -> extractors::Result<Arguments> {
         .next()
         .and_then(|n| n.attr("content"))
         .map(|content| content.replace("&quot;", "\""))
-        .and_then(|data| serde_json::from_str(&data).ok())
-        .ok_or(ExtractionError::InvalidDocument)
+        .ok_or_else(|| ExtractionError::missing_field("arguments").with_path(SELECTOR))?;
+
+    from_json(&data).map_err(|e| e.with_path(SELECTOR))
 }
 
-fn parse_json(json: &str, builder: album::Builder) -> extractors::Result<album::Builder> {
-    let root: Root = serde_json::from_str(json).map_err(|_| ExtractionError::InvalidDocument)?;
+fn parse_json(
+    json: &str,
+    builder: album::Builder,
+    warnings: &mut Vec<Warning>,
+) -> extractors::Result<album::Builder> {
+    let root: Root = from_json(json)?;
 
     let songs = &root.track_list;
 
+    // mora does not give the album kind.
     let kind = guess_album_kind(songs.len());
-    let name = Name::new(root.title.as_str(), LOCALE, true, true);
+
+    warnings.push(Warning::GuessedAlbumKind {
+        kind,
+        reason: format!("{} tracks", songs.len()),
+    });
+
+    let locale = detect_locale(&root.title, HAN_LOCALE);
+    let name = Name::new(root.title.as_str(), locale, true, true);
 
     let builder = builder
         .set_kind(kind)
-        .set_released_on(&parse_release_date(&root.start_date)?)
+        .set_released_on(
+            &parse_release_date(&root.start_date).map_err(|e| e.with_path("startDate"))?,
+        )
         .add_name(name);
 
     let builder = parse_songs(songs, builder)?;
@@ -122,7 +162,8 @@ fn parse_songs(
     mut builder: album::Builder,
 ) -> extractors::Result<album::Builder> {
     for song in songs {
-        let name = Name::new(song.title.as_str(), LOCALE, true, true);
+        let locale = detect_locale(&song.title, HAN_LOCALE);
+        let name = Name::new(song.title.as_str(), locale, true, true);
 
         let song = song::Builder::new()
             .set_position(song.track_no)
@@ -136,20 +177,116 @@ fn parse_songs(
     Ok(builder)
 }
 
+fn parse_json_album_id(json: &str) -> extractors::Result<String> {
+    let root: Root = from_json(json)?;
+    Ok(format!("{}/{}", root.label_code, root.package_id))
+}
+
+/// Searches albums by artist or title.
+pub fn search(client: &dyn Client, query: &str) -> extractors::Result<Vec<Candidate>> {
+    let params = [("keyWord", query)];
+    let url = Url::parse_with_params(SEARCH_ENDPOINT, &params).unwrap();
+    let url = url.as_str();
+
+    let json = fetch(client, url)?;
+
+    parse_package_list(&json).map_err(|e| e.with_url(url))
+}
+
+/// Returns whether the URL is an artist page, e.g., `https://mora.jp/artist/12345/`.
+pub fn is_artist_url(url: &Url) -> bool {
+    url.host_str().map(|h| h == HOST).unwrap_or(false) && url.path().starts_with("/artist/")
+}
+
+/// Lists all albums of an artist.
+///
+/// The artist page loads its albums from the search API, filtered by artist ID. Results are
+/// requested page by page until the total count is reached or a page adds no new albums.
+pub fn discography(client: &dyn Client, url: &Url) -> extractors::Result<Vec<Candidate>> {
+    let artist_id = parse_artist_id(url)?;
+    let mut candidates: Vec<Candidate> = Vec::new();
+
+    for page_no in 1..=extractors::MAX_DISCOGRAPHY_PAGES {
+        let page_no = page_no.to_string();
+        let params = [
+            ("artistId", artist_id.as_str()),
+            ("pageNo", page_no.as_str()),
+        ];
+        let url = Url::parse_with_params(SEARCH_ENDPOINT, &params).unwrap();
+        let url = url.as_str();
+
+        let json = fetch(client, url)?;
+        let (page, total_count) = parse_package_page(&json).map_err(|e| e.with_url(url))?;
+        let mut has_new_packages = false;
+
+        for candidate in page {
+            if !candidates.iter().any(|c| c.url == candidate.url) {
+                candidates.push(candidate);
+                has_new_packages = true;
+            }
+        }
+
+        if !has_new_packages || candidates.len() >= total_count {
+            break;
+        }
+    }
+
+    Ok(candidates)
+}
+
+fn parse_package_list(json: &str) -> extractors::Result<Vec<Candidate>> {
+    parse_package_page(json).map(|(candidates, _)| candidates)
+}
+
+// Parses a page of search results. It returns the candidates and the total number of results.
+fn parse_package_page(json: &str) -> extractors::Result<(Vec<Candidate>, usize)> {
+    let results: SearchResults = from_json(json)?;
+
+    let candidates = results
+        .package_list
+        .into_iter()
+        .map(|package| {
+            let album_id = format!("{}/{}", package.label_code, package.package_id);
+
+            let mut candidate =
+                Candidate::new(Store::Mora, build_html_url(&album_id), package.title);
+
+            candidate.artist_name = Some(package.artist_name);
+            candidate.released_on = parse_release_date(&package.start_date).ok();
+            // Like albums, packages do not give their kind.
+            candidate.kind = Some(guess_album_kind(package.track_count));
+            candidate.track_count = Some(package.track_count);
+
+            candidate
+        })
+        .collect();
+
+    Ok((candidates, results.total_count))
+}
+
 fn parse_album_id(url: &Url) -> extractors::Result<String> {
     let pieces: Vec<&str> = url.path().split('/').filter(|p| !p.is_empty()).collect();
 
     if pieces.len() < 2 {
-        return Err(ExtractionError::InvalidUrl("album ID"));
+        return Err(ExtractionError::new(ErrorKind::InvalidUrl("album ID")).with_url(url.as_str()));
     }
 
     Ok(pieces[pieces.len() - 2..].join("/"))
 }
 
+fn parse_artist_id(url: &Url) -> extractors::Result<String> {
+    let pieces: Vec<&str> = url.path().split('/').filter(|p| !p.is_empty()).collect();
+
+    match pieces.as_slice() {
+        ["artist", artist_id] => Ok(artist_id.to_string()),
+        _ => Err(ExtractionError::new(ErrorKind::InvalidUrl("artist ID")).with_url(url.as_str())),
+    }
+}
+
 fn parse_release_date(s: &str) -> extractors::Result<String> {
     NaiveDate::parse_from_str(s, "%Y/%m/%d %H:%M:%S")
         .map(|d| d.format("%F").to_string())
-        .map_err(|_| ExtractionError::InvalidField("release date"))
+        .map_err(|_| ExtractionError::invalid_field("release date").with_snippet(s))
 }
 
 fn build_json_endpoint(mount_point: &str, label_id: &str, package_id: &str) -> String {
@@ -183,6 +320,8 @@ struct Arguments {
 #[derive(Deserialize)]
 #[serde(rename_all = "camelCase")]
 struct Root {
+    label_code: String,
+    package_id: String,
     start_date: String,
     title: String,
     track_list: Vec<RawSong>,
@@ -196,10 +335,32 @@ struct RawSong {
     track_no: i32,
 }
 
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SearchResults {
+    #[serde(default)]
+    total_count: usize,
+    #[serde(default)]
+    package_list: Vec<RawPackage>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RawPackage {
+    label_code: String,
+    package_id: String,
+    title: String,
+    artist_name: String,
+    start_date: String,
+    track_count: usize,
+}
+
 #[cfg(test)]
 mod tests {
     use std::fs;
 
+    use crate::util::http::MemoryClient;
+
     use super::*;
 
     #[test]
@@ -220,7 +381,18 @@ mod tests {
     #[test]
     fn test_parse() {
         let json = fs::read_to_string("tests/fixtures/mora-43000001-4547366347050.json").unwrap();
-        let album = parse("43000001/4547366347050", &json).unwrap();
+        let Extraction { album, warnings } = parse("43000001/4547366347050", &json).unwrap();
+
+        assert_eq!(
+            warnings,
+            [
+                Warning::GuessedAlbumKind {
+                    kind: album::Kind::Lp,
+                    reason: String::from("10 tracks"),
+                },
+                Warning::MissingArtwork,
+            ]
+        );
 
         assert_eq!(album.kind, album::Kind::Lp);
         assert_eq!(album.country, "JP");
@@ -229,7 +401,7 @@ mod tests {
         assert_eq!(album.url, "https://mora.jp/package/43000001/4547366347050/");
 
         assert_eq!(album.names.len(), 1);
-        assert_eq!(&album.names[0], &Name::new("HONEY", "ja", true, true));
+        assert_eq!(&album.names[0], &Name::new("HONEY", "en", true, true));
 
         assert_eq!(album.songs.len(), 10);
 
@@ -243,6 +415,29 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_extract() {
+        let client = MemoryClient::new();
+        client
+            .insert_file(
+                "https://mora.jp/package/43000001/4547366347050/",
+                "tests/fixtures/mora-43000001-4547366347050.html",
+            )
+            .unwrap();
+        client
+            .insert_file(
+                "https://cf.mora.jp/contents/package/0000/00000068/0011/174/315/packageMeta.json",
+                "tests/fixtures/mora-43000001-4547366347050.json",
+            )
+            .unwrap();
+
+        let extractor = MoraExtractor::new("43000001/4547366347050", Arc::new(client));
+        let album = extractor.extract().unwrap().album;
+
+        assert_eq!(album.names[0].name, "HONEY");
+        assert_eq!(album.songs.len(), 10);
+    }
+
     #[test]
     fn test_parse_html() {
         let html = fs::read_to_string("tests/fixtures/mora-43000001-4547366347050.html").unwrap();
@@ -257,7 +452,121 @@ mod tests {
     #[test]
     fn test_parse_json_with_empty_root() {
         let builder = album::Builder::new();
-        assert!(parse_json("{}", builder).is_err());
+        assert!(parse_json("{}", builder, &mut Vec::new()).is_err());
+    }
+
+    #[test]
+    fn test_parse_json_album_id() {
+        let json = fs::read_to_string("tests/fixtures/mora-43000001-4547366347050.json").unwrap();
+        assert_eq!(
+            parse_json_album_id(&json).unwrap(),
+            "43000001/4547366347050"
+        );
+
+        assert!(parse_json_album_id("{}").is_err());
+    }
+
+    #[test]
+    fn test_search() {
+        let client = MemoryClient::new();
+        client
+            .insert_file(
+                "https://mora.jp/search/api/package?keyWord=HONEY",
+                "tests/fixtures/mora-search.json",
+            )
+            .unwrap();
+
+        let candidates = search(&client, "HONEY").unwrap();
+        assert_eq!(candidates.len(), 2);
+    }
+
+    #[test]
+    fn test_parse_package_list() {
+        let json = fs::read_to_string("tests/fixtures/mora-search.json").unwrap();
+        let candidates = parse_package_list(&json).unwrap();
+
+        assert_eq!(candidates.len(), 2);
+
+        assert_eq!(
+            candidates[0],
+            Candidate {
+                store: Store::Mora,
+                url: String::from("https://mora.jp/package/43000001/4547366347050/"),
+                name: String::from("HONEY"),
+                artist_name: Some(String::from("L'Arc～en～Ciel")),
+                released_on: Some(String::from("2018-02-12")),
+                kind: Some(album::Kind::Lp),
+                track_count: Some(10),
+            }
+        );
+
+        assert_eq!(candidates[1].kind, Some(album::Kind::Single));
+
+        assert!(parse_package_list("{}").unwrap().is_empty());
+        assert!(parse_package_list("<html />").is_err());
+    }
+
+    #[test]
+    fn test_is_artist_url() {
+        let url = Url::parse("https://mora.jp/artist/12345/").unwrap();
+        assert!(is_artist_url(&url));
+
+        let url = Url::parse("https://mora.jp/package/43000001/4547366347050/").unwrap();
+        assert!(!is_artist_url(&url));
+    }
+
+    #[test]
+    fn test_discography() {
+        let client = MemoryClient::new();
+        client
+            .insert_file(
+                "https://mora.jp/search/api/package?artistId=12345&pageNo=1",
+                "tests/fixtures/mora-search.json",
+            )
+            .unwrap();
+
+        let url = Url::parse("https://mora.jp/artist/12345/").unwrap();
+        let candidates = discography(&client, &url).unwrap();
+        assert_eq!(candidates.len(), 2);
+    }
+
+    #[test]
+    fn test_discography_with_pages() {
+        let json = fs::read_to_string("tests/fixtures/mora-search.json").unwrap();
+        let json = json.replace(r#""totalCount": 2"#, r#""totalCount": 4"#);
+
+        let client = MemoryClient::new();
+        client.insert(
+            "https://mora.jp/search/api/package?artistId=12345&pageNo=1",
+            json.clone(),
+        );
+        client.insert(
+            "https://mora.jp/search/api/package?artistId=12345&pageNo=2",
+            json.replace("43000001", "43000002")
+                .replace("10000004", "10000005"),
+        );
+
+        let url = Url::parse("https://mora.jp/artist/12345/").unwrap();
+        let candidates = discography(&client, &url).unwrap();
+        assert_eq!(candidates.len(), 4);
+
+        // A store that ignores the page number serves the first page again.
+        client.insert(
+            "https://mora.jp/search/api/package?artistId=12345&pageNo=2",
+            json,
+        );
+
+        let candidates = discography(&client, &url).unwrap();
+        assert_eq!(candidates.len(), 2);
+    }
+
+    #[test]
+    fn test_parse_artist_id() {
+        let url = Url::parse("https://mora.jp/artist/12345/").unwrap();
+        assert_eq!(parse_artist_id(&url).unwrap(), "12345");
+
+        let url = Url::parse("https://mora.jp/artist/").unwrap();
+        assert!(parse_artist_id(&url).is_err());
     }
 
     #[test]
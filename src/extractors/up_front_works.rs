@@ -1,3 +1,5 @@
+use std::sync::Arc;
+
 use chrono::NaiveDate;
 use select::{
     document::Document,
@@ -6,19 +8,26 @@ use select::{
 use url::Url;
 
 use crate::{
-    extractors::{self, ExtractionError, Extractor},
-    models::{album, song, Album, Name},
+    extractors::{
+        self, fetch, Candidate, Documents, ErrorKind, Extraction, ExtractionError, Extractor, Store,
+    },
+    models::{album, song, Name},
+    util::{http::Client, script::detect_locale},
 };
 
 static HOST: &str = "www.up-front-works.jp";
 
 static BASE_URL: &str = "http://www.up-front-works.jp/release/detail";
+static SEARCH_URL: &str = "http://www.up-front-works.jp/release/search/";
+static ARTIST_PATH: &str = "/release/search/";
 
 static COUNTRY: &str = "JP";
-static LOCALE: &str = "ja";
+// Titles with only Han characters are assumed to be Japanese.
+static HAN_LOCALE: &str = "ja";
 
 pub struct UpFrontWorksExtractor {
     album_id: String,
+    client: Arc<dyn Client>,
 }
 
 impl UpFrontWorksExtractor {
@@ -26,44 +35,63 @@ impl UpFrontWorksExtractor {
         url.host_str().map(|h| h == HOST).unwrap_or(false)
     }
 
-    pub fn from_url(url: &Url) -> extractors::Result<UpFrontWorksExtractor> {
-        parse_album_id(url).map(UpFrontWorksExtractor::new)
+    pub fn from_url(
+        url: &Url,
+        client: Arc<dyn Client>,
+    ) -> extractors::Result<UpFrontWorksExtractor> {
+        parse_album_id(url).map(|id| UpFrontWorksExtractor::new(id, client))
     }
 
-    pub fn new<I>(album_id: I) -> UpFrontWorksExtractor
+    pub fn new<I>(album_id: I, client: Arc<dyn Client>) -> UpFrontWorksExtractor
     where
         I: Into<String>,
     {
         UpFrontWorksExtractor {
             album_id: album_id.into(),
+            client,
         }
     }
 
-    fn fetch(&self) -> extractors::Result<String> {
+    fn fetch_html(&self) -> extractors::Result<String> {
         let url = format!("{}/{}/", BASE_URL, self.album_id);
-
-        ureq::get(&url)
-            .call()
-            .map_err(|e| ExtractionError::FetchRequest(Box::new(e)))
-            .and_then(|r| r.into_string().map_err(ExtractionError::FetchBody))
+        fetch(self.client.as_ref(), &url)
     }
 }
 
 impl Extractor for UpFrontWorksExtractor {
-    fn extract(&self) -> extractors::Result<Album> {
-        let html = self.fetch()?;
-        parse(&self.album_id, &html)
+    fn extract(&self) -> extractors::Result<Extraction> {
+        let html = self
+            .fetch_html()
+            .map_err(|e| e.with_store(Store::UpFrontWorks))?;
+
+        parse(&self.album_id, &html).map_err(|e| e.with_store(Store::UpFrontWorks))
     }
 }
 
-fn parse(album_id: &str, html: &str) -> extractors::Result<Album> {
+/// Parses a saved release page.
+///
+/// The album ID, i.e., the product number (e.g., "EPCE-7387"), is required.
+pub fn parse_documents(documents: &Documents) -> extractors::Result<Extraction> {
+    let html = documents
+        .html
+        .as_ref()
+        .ok_or(ErrorKind::MissingDocument("html"))?;
+    let album_id = documents
+        .album_id
+        .as_ref()
+        .ok_or_else(|| ExtractionError::missing_field("album ID"))?;
+
+    parse(album_id, html)
+}
+
+fn parse(album_id: &str, html: &str) -> extractors::Result<Extraction> {
     let url = format!("{}/{}/", BASE_URL, album_id);
 
     let builder = album::Builder::new().set_country(COUNTRY).set_url(&url);
 
-    let builder = parse_html(html, builder)?;
+    let builder = parse_html(html, builder).map_err(|e| e.with_url(url))?;
 
-    Ok(builder.build())
+    Ok(Extraction::new(builder.build(), Vec::new()))
 }
 
 fn parse_html(html: &str, builder: album::Builder) -> extractors::Result<album::Builder> {
@@ -72,23 +100,27 @@ fn parse_html(html: &str, builder: album::Builder) -> extractors::Result<album::
     let name = document
         .find(Class("product_title"))
         .next()
-        .ok_or(ExtractionError::MissingField("name"))
+        .ok_or_else(|| ExtractionError::missing_field("name").with_path(".product_title"))
         .map(|n| n.text())
-        .map(|n| Name::new(n, LOCALE, true, true))?;
+        .map(|n| build_name(&n))?;
+
+    static META_SELECTOR: &str = ".data1 .columnB";
 
     let mut meta_node = document.find(Descendant(Class("data1"), Class("columnB")));
 
     let kind = meta_node
         .next()
-        .ok_or(ExtractionError::MissingField("kind"))
+        .ok_or_else(|| ExtractionError::missing_field("kind"))
         .map(|n| n.text())
-        .and_then(|kind| parse_kind(&kind))?;
+        .and_then(|kind| parse_kind(&kind))
+        .map_err(|e| e.with_path(META_SELECTOR))?;
 
     let released_on = meta_node
         .next()
-        .ok_or(ExtractionError::MissingField("release date"))
+        .ok_or_else(|| ExtractionError::missing_field("release date"))
         .map(|n| n.text())
-        .and_then(|date| parse_release_date(&date))?;
+        .and_then(|date| parse_release_date(&date))
+        .map_err(|e| e.with_path(META_SELECTOR))?;
 
     let builder = builder
         .set_kind(kind)
@@ -107,7 +139,7 @@ fn parse_songs(
     let table = document
         .find(Class("data2"))
         .next()
-        .ok_or(ExtractionError::MissingField("songs"))?;
+        .ok_or_else(|| ExtractionError::missing_field("songs").with_path(".data2"))?;
 
     let rows = table
         .find(predicate::Name("tr"))
@@ -120,25 +152,31 @@ fn parse_songs(
             continue;
         }
 
+        let path = format!(".data2 tr:nth-child({})", i + 2);
         let mut cells = row.find(predicate::Name("td"));
 
         let position = cells
             .next()
-            .ok_or(ExtractionError::MissingField("songs[_].track_number"))
+            .ok_or_else(|| ExtractionError::missing_field("songs[_].track_number"))
             .map(|n| n.text())
-            .and_then(|s| parse_position(&s))?;
+            .and_then(|s| parse_position(&s))
+            .map_err(|e| e.with_path(format!("{} td:nth-child(1)", path)))?;
 
         let name = cells
             .next()
-            .ok_or(ExtractionError::MissingField("songs[_].name"))
+            .ok_or_else(|| {
+                ExtractionError::missing_field("songs[_].name")
+                    .with_path(format!("{} td:nth-child(2)", path))
+            })
             .map(|n| n.text())
-            .map(|n| Name::new(n, LOCALE, true, true))?;
+            .map(|n| build_name(&n))?;
 
         let duration = cells
             .next()
-            .ok_or(ExtractionError::MissingField("songs[_].duration"))
+            .ok_or_else(|| ExtractionError::missing_field("songs[_].duration"))
             .map(|n| n.text())
-            .and_then(|s| parse_duration(&s))?;
+            .and_then(|s| parse_duration(&s))
+            .map_err(|e| e.with_path(format!("{} td:nth-child(3)", path)))?;
 
         let song = song::Builder::new()
             .set_position(position)
@@ -152,9 +190,14 @@ fn parse_songs(
     Ok(builder)
 }
 
+fn build_name(s: &str) -> Name {
+    let locale = detect_locale(s, HAN_LOCALE);
+    Name::new(s, locale, true, true)
+}
+
 fn parse_position(s: &str) -> extractors::Result<i32> {
     s.parse()
-        .map_err(|_| ExtractionError::InvalidField("position"))
+        .map_err(|_| ExtractionError::invalid_field("position").with_snippet(s))
 }
 
 fn parse_duration(s: &str) -> extractors::Result<i32> {
@@ -162,23 +205,146 @@ fn parse_duration(s: &str) -> extractors::Result<i32> {
 
     let minutes: i32 = pieces
         .next()
-        .ok_or(ExtractionError::MissingField("duration.minutes"))
-        .and_then(|s| {
-            s.parse()
-                .map_err(|_| ExtractionError::InvalidField("duration.minutes"))
+        .ok_or_else(|| ExtractionError::missing_field("duration.minutes"))
+        .and_then(|m| {
+            m.parse()
+                .map_err(|_| ExtractionError::invalid_field("duration.minutes").with_snippet(s))
         })?;
 
     let seconds: i32 = pieces
         .next()
-        .ok_or(ExtractionError::MissingField("duration.seconds"))
-        .and_then(|s| {
-            s.parse()
-                .map_err(|_| ExtractionError::InvalidField("duration.seconds"))
+        .ok_or_else(|| ExtractionError::missing_field("duration.seconds").with_snippet(s))
+        .and_then(|t| {
+            t.parse()
+                .map_err(|_| ExtractionError::invalid_field("duration.seconds").with_snippet(s))
         })?;
 
     Ok(minutes * 60 + seconds)
 }
 
+/// Searches releases by artist or title.
+///
+/// This is a keyword search of all releases. The release list of an artist needs its numeric ID,
+/// which cannot be looked up by name, so it is only used to crawl an artist page (see
+/// `discography`).
+pub fn search(client: &dyn Client, query: &str) -> extractors::Result<Vec<Candidate>> {
+    let params = [("keyword", query)];
+    let url = Url::parse_with_params(SEARCH_URL, &params).unwrap();
+    let url = url.as_str();
+
+    let html = fetch(client, url)?;
+
+    parse_release_list(&html).map_err(|e| e.with_url(url))
+}
+
+/// Returns whether the URL is the release list of an artist, e.g.,
+/// `http://www.up-front-works.jp/release/search/?artist=12`.
+pub fn is_artist_url(url: &Url) -> bool {
+    url.host_str().map(|h| h == HOST).unwrap_or(false)
+        && url.path() == ARTIST_PATH
+        && url.query_pairs().any(|(key, _)| key == "artist")
+}
+
+/// Lists all releases of an artist.
+///
+/// The release list is requested page by page until a page has no new releases or is not found.
+pub fn discography(client: &dyn Client, url: &Url) -> extractors::Result<Vec<Candidate>> {
+    let mut candidates: Vec<Candidate> = Vec::new();
+
+    for page in 1..=extractors::MAX_DISCOGRAPHY_PAGES {
+        let url = build_page_url(url, page);
+        let url = url.as_str();
+
+        let html = match fetch(client, url) {
+            Ok(html) => html,
+            Err(e) if page > 1 && e.status() == Some(404) => break,
+            Err(e) => return Err(e),
+        };
+        let releases = parse_release_list(&html).map_err(|e| e.with_url(url))?;
+
+        let mut is_last_page = true;
+
+        for release in releases {
+            if !candidates.iter().any(|c| c.url == release.url) {
+                candidates.push(release);
+                is_last_page = false;
+            }
+        }
+
+        if is_last_page {
+            break;
+        }
+    }
+
+    Ok(candidates)
+}
+
+fn build_page_url(url: &Url, page: usize) -> Url {
+    let pairs: Vec<(String, String)> = url
+        .query_pairs()
+        .filter(|(key, _)| key != "page")
+        .map(|(key, value)| (key.into_owned(), value.into_owned()))
+        .collect();
+
+    let mut url = url.clone();
+
+    url.query_pairs_mut()
+        .clear()
+        .extend_pairs(pairs)
+        .append_pair("page", &page.to_string());
+
+    url
+}
+
+/// Parses a list of releases, e.g., search results or the releases of an artist.
+///
+/// Release lists do not include track counts.
+fn parse_release_list(html: &str) -> extractors::Result<Vec<Candidate>> {
+    let document = Document::from(html);
+    let mut candidates = Vec::new();
+
+    for node in document.find(Descendant(Class("release_list"), predicate::Name("li"))) {
+        let album_id = node
+            .find(predicate::Name("a"))
+            .next()
+            .and_then(|n| n.attr("href"))
+            .and_then(|href| href.split('/').rfind(|p| !p.is_empty()))
+            .ok_or_else(|| {
+                ExtractionError::missing_field("album ID").with_path(".release_list li a")
+            })?;
+
+        let name = node
+            .find(Class("release_title"))
+            .next()
+            .map(|n| n.text().trim().to_string())
+            .ok_or_else(|| {
+                ExtractionError::missing_field("name").with_path(".release_list li .release_title")
+            })?;
+
+        let url = format!("{}/{}/", BASE_URL, album_id);
+        let mut candidate = Candidate::new(Store::UpFrontWorks, url, name);
+
+        candidate.artist_name = node
+            .find(Class("release_artist"))
+            .next()
+            .map(|n| n.text().trim().to_string());
+
+        candidate.kind = node
+            .find(Class("release_type"))
+            .next()
+            .and_then(|n| parse_kind(n.text().trim()).ok());
+
+        candidate.released_on = node
+            .find(Class("release_date"))
+            .next()
+            .and_then(|n| parse_release_date(n.text().trim()).ok());
+
+        candidates.push(candidate);
+    }
+
+    Ok(candidates)
+}
+
 fn parse_album_id(url: &Url) -> extractors::Result<String> {
     url.path()
         .split('/')
@@ -191,7 +357,9 @@ fn parse_album_id(url: &Url) -> extractors::Result<String> {
                 None
             }
         })
-        .ok_or(ExtractionError::InvalidUrl("album ID"))
+        .ok_or_else(|| {
+            ExtractionError::new(ErrorKind::InvalidUrl("album ID")).with_url(url.as_str())
+        })
 }
 
 fn parse_kind(s: &str) -> extractors::Result<album::Kind> {
@@ -199,20 +367,22 @@ fn parse_kind(s: &str) -> extractors::Result<album::Kind> {
         "CDシングル" => Ok(album::Kind::Single),
         "CDミニアルバム" => Ok(album::Kind::Ep),
         "CDアルバム" => Ok(album::Kind::Lp),
-        _ => Err(ExtractionError::InvalidField("kind")),
+        _ => Err(ExtractionError::invalid_field("kind").with_snippet(s)),
     }
 }
 
 fn parse_release_date(s: &str) -> extractors::Result<String> {
     NaiveDate::parse_from_str(s, "%Y/%m/%d")
         .map(|d| d.format("%F").to_string())
-        .map_err(|_| ExtractionError::InvalidField("release date"))
+        .map_err(|_| ExtractionError::invalid_field("release date").with_snippet(s))
 }
 
 #[cfg(test)]
 mod tests {
     use std::fs;
 
+    use crate::{extractors::Warning, util::http::MemoryClient};
+
     use super::*;
 
     #[test]
@@ -233,7 +403,9 @@ mod tests {
     #[test]
     fn test_parse() {
         let html = fs::read_to_string("tests/fixtures/up-front-works-epce-7387.html").unwrap();
-        let album = parse("EPCE-7387", &html).unwrap();
+        let Extraction { album, warnings } = parse("EPCE-7387", &html).unwrap();
+
+        assert_eq!(warnings, [Warning::MissingArtwork]);
 
         assert_eq!(album.kind, album::Kind::Ep);
         assert_eq!(album.country, "JP");
@@ -276,6 +448,129 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_extract() {
+        let client = MemoryClient::new();
+        client
+            .insert_file(
+                "http://www.up-front-works.jp/release/detail/EPCE-7387/",
+                "tests/fixtures/up-front-works-epce-7387.html",
+            )
+            .unwrap();
+
+        let extractor = UpFrontWorksExtractor::new("EPCE-7387", Arc::new(client));
+        let album = extractor.extract().unwrap().album;
+
+        assert_eq!(album.names[0].name, "二十歳のモーニング娘。");
+        assert_eq!(album.songs.len(), 8);
+
+        let extractor = UpFrontWorksExtractor::new("EPCE-0000", Arc::new(MemoryClient::new()));
+        let e = extractor.extract().err().unwrap();
+        assert_eq!(e.store(), Some(Store::UpFrontWorks));
+        assert_eq!(
+            e.url(),
+            Some("http://www.up-front-works.jp/release/detail/EPCE-0000/")
+        );
+        assert_eq!(e.status(), Some(404));
+    }
+
+    #[test]
+    fn test_search() {
+        let client = MemoryClient::new();
+        client
+            .insert_file(
+                "http://www.up-front-works.jp/release/search/?keyword=%E3%83%A2%E3%83%BC%E3%83%8B%E3%83%B3%E3%82%B0%E5%A8%98%E3%80%82",
+                "tests/fixtures/up-front-works-search.html",
+            )
+            .unwrap();
+
+        let candidates = search(&client, "モーニング娘。").unwrap();
+        assert_eq!(candidates.len(), 2);
+    }
+
+    #[test]
+    fn test_is_artist_url() {
+        let url = Url::parse("http://www.up-front-works.jp/release/search/?artist=12").unwrap();
+        assert!(is_artist_url(&url));
+
+        let url = Url::parse("http://www.up-front-works.jp/release/detail/EPCE-7387/").unwrap();
+        assert!(!is_artist_url(&url));
+
+        let url = Url::parse("http://www.up-front-works.jp/release/search/?keyword=HONEY").unwrap();
+        assert!(!is_artist_url(&url));
+
+        let url = Url::parse("http://www.up-front-works.jp/release/").unwrap();
+        assert!(!is_artist_url(&url));
+    }
+
+    #[test]
+    fn test_discography() {
+        let client = MemoryClient::new();
+        client
+            .insert_file(
+                "http://www.up-front-works.jp/release/search/?artist=12&page=1",
+                "tests/fixtures/up-front-works-search.html",
+            )
+            .unwrap();
+
+        // The last page is followed by a missing page.
+        let url = Url::parse("http://www.up-front-works.jp/release/search/?artist=12").unwrap();
+        let candidates = discography(&client, &url).unwrap();
+        assert_eq!(candidates.len(), 2);
+
+        // The last page is repeated.
+        client
+            .insert_file(
+                "http://www.up-front-works.jp/release/search/?artist=12&page=2",
+                "tests/fixtures/up-front-works-search.html",
+            )
+            .unwrap();
+
+        let candidates = discography(&client, &url).unwrap();
+        assert_eq!(candidates.len(), 2);
+    }
+
+    #[test]
+    fn test_build_page_url() {
+        let url = Url::parse("http://www.up-front-works.jp/release/search/?artist=12").unwrap();
+        assert_eq!(
+            build_page_url(&url, 2).as_str(),
+            "http://www.up-front-works.jp/release/search/?artist=12&page=2"
+        );
+
+        let url =
+            Url::parse("http://www.up-front-works.jp/release/search/?page=3&artist=12").unwrap();
+        assert_eq!(
+            build_page_url(&url, 1).as_str(),
+            "http://www.up-front-works.jp/release/search/?artist=12&page=1"
+        );
+    }
+
+    #[test]
+    fn test_parse_release_list() {
+        let html = fs::read_to_string("tests/fixtures/up-front-works-search.html").unwrap();
+        let candidates = parse_release_list(&html).unwrap();
+
+        assert_eq!(candidates.len(), 2);
+
+        assert_eq!(
+            candidates[0],
+            Candidate {
+                store: Store::UpFrontWorks,
+                url: String::from("http://www.up-front-works.jp/release/detail/EPCE-7387/"),
+                name: String::from("二十歳のモーニング娘。"),
+                artist_name: Some(String::from("モーニング娘。'18")),
+                released_on: Some(String::from("2018-02-07")),
+                kind: Some(album::Kind::Ep),
+                track_count: None,
+            }
+        );
+
+        assert_eq!(candidates[1].kind, Some(album::Kind::Single));
+
+        assert!(parse_release_list("<html />").unwrap().is_empty());
+    }
+
     #[test]
     fn test_parse_position() {
         assert_eq!(parse_position("1").unwrap(), 1);
@@ -0,0 +1,79 @@
+use std::fmt;
+
+use serde::Serialize;
+
+use crate::models::album;
+
+/// A non-fatal issue found while extracting an album.
+///
+/// Warnings mark values that were guessed or changed by an extractor and should be reviewed.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
+#[serde(tag = "type", rename_all = "kebab-case")]
+pub enum Warning {
+    /// The store does not give the album kind, or its kind does not map to one.
+    GuessedAlbumKind { kind: album::Kind, reason: String },
+    /// A name was changed from the one given by the store, e.g., to replace backticks.
+    NormalizedName {
+        original: String,
+        normalized: String,
+    },
+    /// The store does not give an artwork URL.
+    MissingArtwork,
+    /// The lyrics of a song could not be fetched.
+    MissingLyrics { position: i32, reason: String },
+}
+
+impl fmt::Display for Warning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::GuessedAlbumKind { kind, reason } => {
+                write!(f, "guessed album kind '{}' ({})", kind, reason)
+            }
+            Self::NormalizedName {
+                original,
+                normalized,
+            } => write!(f, "normalized name '{}' to '{}'", original, normalized),
+            Self::MissingArtwork => f.write_str("missing artwork"),
+            Self::MissingLyrics { position, reason } => {
+                write!(f, "missing lyrics for track {} ({})", position, reason)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fmt() {
+        let warning = Warning::GuessedAlbumKind {
+            kind: album::Kind::Single,
+            reason: String::from("store kind 'OST'"),
+        };
+        assert_eq!(
+            warning.to_string(),
+            "guessed album kind 'single' (store kind 'OST')"
+        );
+
+        assert_eq!(Warning::MissingArtwork.to_string(), "missing artwork");
+    }
+
+    #[test]
+    fn test_serialize() {
+        let warning = Warning::NormalizedName {
+            original: String::from("Girl`s Talk"),
+            normalized: String::from("Girl's Talk"),
+        };
+
+        assert_eq!(
+            serde_json::to_string(&warning).unwrap(),
+            r#"{"type":"normalized-name","original":"Girl`s Talk","normalized":"Girl's Talk"}"#
+        );
+
+        assert_eq!(
+            serde_json::to_string(&Warning::MissingArtwork).unwrap(),
+            r#"{"type":"missing-artwork"}"#
+        );
+    }
+}
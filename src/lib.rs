@@ -1,8 +1,11 @@
 pub use crate::extractors::{Extractor, MelonExtractor, MoraExtractor};
 pub use crate::models::Name;
 
+pub mod catalog;
+pub mod config;
 pub mod editor;
 pub mod extractors;
+pub mod manifest;
 pub mod models;
 pub mod renderer;
 pub mod util;
@@ -1,18 +1,43 @@
 use std::{
+    cell::RefCell,
     collections::HashSet,
+    env,
+    fs::{self, File},
+    io,
     path::{Path, PathBuf},
+    process,
+    sync::Arc,
+    time::Duration,
 };
 
-use clap::{crate_name, value_t, App, Arg};
+use clap::{crate_name, value_t, App, AppSettings, Arg, ArgMatches, SubCommand};
 use git_testament::{git_testament, render_testament};
 use glob::glob;
 use log::{log_enabled, warn, Level, LevelFilter};
+use serde::Serialize;
 use url::Url;
 
-use multimeta::{editor, extractors, renderer::Renderer, writer::Writer};
+use multimeta::{
+    catalog::Catalog,
+    config::Config,
+    editor::{self, Edits},
+    extractors::{self, Candidate, Documents, Extraction, ExtractionError, Store, Warning},
+    manifest::Manifest,
+    renderer::Renderer,
+    util::http::{
+        CacheClient, Client, Downloader, RecordClient, ReplayClient, RetryClient, ThrottleClient,
+        UreqClient,
+    },
+    writer::Writer,
+};
 
 git_testament!(TESTAMENT);
 
+const DEFAULT_CACHE_TTL: &str = "86400"; // seconds
+const DEFAULT_TIMEOUT: &str = "30"; // seconds
+const DEFAULT_CONNECT_TIMEOUT: &str = "10"; // seconds
+const DEFAULT_RETRIES: &str = "3";
+
 // This is required to be `Fn(String) -> _` to be used as a clap validator.
 #[allow(clippy::needless_pass_by_value)]
 fn validate_output(s: String) -> Result<(), String> {
@@ -25,6 +50,79 @@ fn validate_output(s: String) -> Result<(), String> {
     }
 }
 
+/// Reads the config from the given path or, if unset, the user config directory, e.g.,
+/// `~/.config/multimeta/config.toml`. A missing default config is not an error.
+fn load_config(src: Option<&str>) -> anyhow::Result<Config> {
+    if let Some(src) = src {
+        return Ok(Config::load(src)?);
+    }
+
+    let src = match dirs::config_dir() {
+        Some(dir) => dir.join(crate_name!()).join("config.toml"),
+        None => return Ok(Config::default()),
+    };
+
+    if src.exists() {
+        Ok(Config::load(src)?)
+    } else {
+        Ok(Config::default())
+    }
+}
+
+fn default_cache_dir() -> PathBuf {
+    dirs::cache_dir()
+        .unwrap_or_else(env::temp_dir)
+        .join(crate_name!())
+}
+
+/// Returns the directory of in-progress edits, e.g., `~/.local/share/multimeta/autosave`.
+fn default_autosave_dir() -> PathBuf {
+    dirs::data_local_dir()
+        .unwrap_or_else(env::temp_dir)
+        .join(crate_name!())
+        .join("autosave")
+}
+
+/// Returns the file of the prompt history, e.g., `~/.local/share/multimeta/history.txt`.
+fn default_history_file() -> PathBuf {
+    dirs::data_local_dir()
+        .unwrap_or_else(env::temp_dir)
+        .join(crate_name!())
+        .join("history.txt")
+}
+
+/// Wraps the base client with the response cache and recorder, or replaces it with a replay of a
+/// recording.
+fn build_extractor_client(
+    matches: &ArgMatches<'_>,
+    client: Arc<dyn Client>,
+) -> anyhow::Result<Arc<dyn Client>> {
+    if let Some(dir) = matches.value_of("replay") {
+        return Ok(Arc::new(ReplayClient::open(dir)?));
+    }
+
+    let mut client = client;
+
+    if !matches.is_present("no-cache") {
+        let cache_dir = matches
+            .value_of("cache-dir")
+            .map(PathBuf::from)
+            .unwrap_or_else(default_cache_dir);
+        let ttl = value_t!(matches, "cache-ttl", u64).unwrap_or_else(|e| e.exit());
+
+        let cache_client = CacheClient::new(client, cache_dir, Duration::from_secs(ttl))
+            .with_refresh(matches.is_present("refresh"));
+
+        client = Arc::new(cache_client);
+    }
+
+    if let Some(dir) = matches.value_of("record") {
+        client = Arc::new(RecordClient::new(client, dir)?);
+    }
+
+    Ok(client)
+}
+
 fn get_artists<P>(output_dir: P) -> HashSet<String>
 where
     P: AsRef<Path>,
@@ -68,9 +166,45 @@ where
     artists.contains(artist_id)
 }
 
-fn main() -> anyhow::Result<()> {
+/// Prints an error to stderr, with the context of an extraction error, e.g., the URL and the
+/// location in the document.
+fn report(e: &anyhow::Error) {
+    eprintln!("error: {}", e);
+
+    if let Some(e) = e.downcast_ref::<ExtractionError>() {
+        if let Some(url) = e.url() {
+            eprintln!("  url: {}", url);
+        }
+
+        if let Some(status) = e.status() {
+            eprintln!("  status: {}", status);
+        }
+
+        if let Some(path) = e.path() {
+            eprintln!("  at: {}", path);
+        }
+
+        if let Some(snippet) = e.snippet() {
+            eprintln!("  found: {}", snippet);
+        }
+    }
+
+    for cause in e.chain().skip(1) {
+        eprintln!("  caused by: {}", cause);
+    }
+}
+
+fn main() {
+    if let Err(e) = run() {
+        report(&e);
+        process::exit(1);
+    }
+}
+
+fn run() -> anyhow::Result<()> {
     let matches = App::new(crate_name!())
         .version(render_testament!(TESTAMENT).as_str())
+        .setting(AppSettings::SubcommandsNegateReqs)
         .arg(
             Arg::with_name("output")
                 .short("o")
@@ -86,6 +220,123 @@ fn main() -> anyhow::Result<()> {
                 .long("verbose")
                 .help("Use verbose logging"),
         )
+        .arg(
+            Arg::with_name("config")
+                .short("c")
+                .long("config")
+                .value_name("FILE")
+                .help("Set the config file"),
+        )
+        .arg(
+            Arg::with_name("proxy")
+                .long("proxy")
+                .value_name("URL")
+                .help("Use a proxy for all requests, e.g., user:password@localhost:8080"),
+        )
+        .arg(
+            Arg::with_name("timeout")
+                .long("timeout")
+                .value_name("SECONDS")
+                .help("Set the timeout of a request, including reading the response")
+                .default_value(DEFAULT_TIMEOUT),
+        )
+        .arg(
+            Arg::with_name("connect-timeout")
+                .long("connect-timeout")
+                .value_name("SECONDS")
+                .help("Set the timeout of connecting to a host")
+                .default_value(DEFAULT_CONNECT_TIMEOUT),
+        )
+        .arg(
+            Arg::with_name("retries")
+                .long("retries")
+                .value_name("N")
+                .help("Set how many times a request is retried after a connection or server error")
+                .default_value(DEFAULT_RETRIES),
+        )
+        .arg(
+            Arg::with_name("no-cache")
+                .long("no-cache")
+                .help("Do not read or write cached responses"),
+        )
+        .arg(
+            Arg::with_name("refresh")
+                .long("refresh")
+                .help("Ignore cached responses but update the cache")
+                .conflicts_with("no-cache"),
+        )
+        .arg(
+            Arg::with_name("cache-dir")
+                .long("cache-dir")
+                .value_name("DIR")
+                .help("Set the response cache directory"),
+        )
+        .arg(
+            Arg::with_name("cache-ttl")
+                .long("cache-ttl")
+                .value_name("SECONDS")
+                .help("Set how long cached responses are used")
+                .default_value(DEFAULT_CACHE_TTL),
+        )
+        .arg(
+            Arg::with_name("record")
+                .long("record")
+                .value_name("DIR")
+                .help("Save all store responses to a directory"),
+        )
+        .arg(
+            Arg::with_name("replay")
+                .long("replay")
+                .value_name("DIR")
+                .help("Serve store responses from a directory saved by --record")
+                .conflicts_with_all(&["record", "refresh"]),
+        )
+        .arg(
+            Arg::with_name("lyrics")
+                .long("lyrics")
+                .help("Fetch song lyrics, if supported by the store"),
+        )
+        .arg(
+            Arg::with_name("report")
+                .long("report")
+                .value_name("FILE")
+                .help(
+                "Write a JSON report of the albums and their extraction warnings (- for stdout)",
+            ),
+        )
+        .arg(
+            Arg::with_name("yes")
+                .short("y")
+                .long("yes")
+                .visible_alias("no-edit")
+                .help("Write albums without the editor, failing if an ID cannot be generated"),
+        )
+        .arg(
+            Arg::with_name("editor")
+                .long("editor")
+                .help("Edit albums as TOML documents in $VISUAL or $EDITOR")
+                .conflicts_with("yes"),
+        )
+        .arg(
+            Arg::with_name("tui")
+                .long("tui")
+                .help("Edit albums in a full-screen editor")
+                .conflicts_with_all(&["yes", "editor"]),
+        )
+        .arg(
+            Arg::with_name("guess-names")
+                .long("guess-names")
+                .help("Guess names, e.g., romanizations, of albums and songs (with --yes)")
+                .requires("yes"),
+        )
+        .arg(
+            Arg::with_name("edits")
+                .long("edits")
+                .value_name("FILE")
+                .help(
+                    "Apply edits from a TOML or JSON file before the editor (single albums only)",
+                ),
+        )
         .arg(
             Arg::with_name("artist-id")
                 .help("The local artist ID")
@@ -98,6 +349,82 @@ fn main() -> anyhow::Result<()> {
                 .index(2)
                 .required(true),
         )
+        .subcommand(
+            SubCommand::with_name("parse")
+                .about("Parses saved store pages instead of fetching them")
+                .arg(
+                    Arg::with_name("store")
+                        .help("The store the pages were saved from")
+                        .possible_values(&Store::NAMES)
+                        .index(1)
+                        .required(true),
+                )
+                .arg(
+                    Arg::with_name("artist-id")
+                        .help("The local artist ID")
+                        .index(2)
+                        .required(true),
+                )
+                .arg(
+                    Arg::with_name("html")
+                        .long("html")
+                        .value_name("FILE")
+                        .help("The saved album page (melon, up-front-works)"),
+                )
+                .arg(
+                    Arg::with_name("json")
+                        .long("json")
+                        .value_name("FILE")
+                        .help("The saved album JSON (melon, mora)"),
+                )
+                .arg(
+                    Arg::with_name("album-id")
+                        .long("album-id")
+                        .value_name("ID")
+                        .help(
+                            "The store album ID, if it cannot be read from the pages \
+                             (required for up-front-works)",
+                        ),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("batch")
+                .about("Scrapes all albums listed in a manifest")
+                .arg(
+                    Arg::with_name("manifest")
+                        .help("A TOML manifest (.toml) or a list of <artist-id> <url> lines")
+                        .index(1)
+                        .required(true),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("search")
+                .about("Searches stores for albums and scrapes the picked one")
+                .arg(
+                    Arg::with_name("store")
+                        .long("store")
+                        .value_name("STORE")
+                        .help("Search only the given stores (default: all)")
+                        .possible_values(&Store::NAMES)
+                        .multiple(true)
+                        .number_of_values(1),
+                )
+                .arg(
+                    Arg::with_name("artist-id")
+                        .long("artist-id")
+                        .value_name("ID")
+                        .help(
+                            "The local artist ID. If unset, it is prompted for after picking, \
+                             or candidates are only listed with --yes.",
+                        ),
+                )
+                .arg(
+                    Arg::with_name("query")
+                        .help("The artist or album name to search for")
+                        .multiple(true)
+                        .required(true),
+                ),
+        )
         .get_matches();
 
     if matches.is_present("verbose") {
@@ -110,28 +437,452 @@ fn main() -> anyhow::Result<()> {
 
     let output_dir = value_t!(matches, "output", PathBuf).unwrap_or_else(|e| e.exit());
 
+    let config = load_config(matches.value_of("config"))?;
+    let report_dst = matches.value_of("report");
+
+    let timeout = value_t!(matches, "timeout", u64).unwrap_or_else(|e| e.exit());
+    let connect_timeout = value_t!(matches, "connect-timeout", u64).unwrap_or_else(|e| e.exit());
+    let retries = value_t!(matches, "retries", u32).unwrap_or_else(|e| e.exit());
+
+    let mut client_builder = UreqClient::builder()
+        .set_timeout(Duration::from_secs(timeout))
+        .set_connect_timeout(Duration::from_secs(connect_timeout));
+
+    if let Some(proxy) = matches.value_of("proxy") {
+        client_builder = client_builder.set_proxy(proxy);
+    }
+
+    let client = ThrottleClient::new(Arc::new(client_builder.build()?), config.limits());
+    let client = RetryClient::new(Arc::new(client)).with_max_retries(retries);
+    let client: Arc<dyn Client> = Arc::new(client);
+
+    let options = extractors::Options {
+        lyrics: matches.is_present("lyrics"),
+    };
+
+    let edit_mode = if matches.is_present("yes") {
+        EditMode::Auto {
+            guess_names: matches.is_present("guess-names"),
+        }
+    } else if matches.is_present("editor") {
+        EditMode::External
+    } else if matches.is_present("tui") {
+        EditMode::FullScreen
+    } else {
+        EditMode::Interactive
+    };
+
+    if edit_mode == EditMode::Interactive {
+        editor::set_history_file(default_history_file());
+    }
+
+    let edits = match matches.value_of("edits") {
+        Some(src) => Some(Edits::load(src)?),
+        None => None,
+    };
+
+    let extractor_client = build_extractor_client(&matches, client.clone())?;
+
+    let scraper = Scraper {
+        output_dir: &output_dir,
+        client,
+        extractor_client,
+        options,
+        edits,
+        edit_mode,
+        autosave_dir: default_autosave_dir(),
+        report_dst,
+        reports: RefCell::new(Vec::new()),
+    };
+
+    let result = dispatch(&matches, &scraper);
+
+    // The report is written even if an album fails, e.g., in a batch or crawl.
+    let report_result = scraper.write_report();
+
+    result.and(report_result)
+}
+
+/// Runs the subcommand, or scrapes the album or artist page given as arguments.
+fn dispatch(matches: &ArgMatches<'_>, scraper: &Scraper<'_>) -> anyhow::Result<()> {
+    if let ("parse", Some(parse_matches)) = matches.subcommand() {
+        let artist_id = parse_matches.value_of("artist-id").unwrap();
+        warn_missing_artist_id(scraper.output_dir, artist_id);
+
+        let extraction = parse(parse_matches)?;
+        return scraper.write(artist_id, extraction);
+    }
+
+    if let ("batch", Some(batch_matches)) = matches.subcommand() {
+        let manifest = Manifest::load(batch_matches.value_of("manifest").unwrap())?;
+        return scraper.batch(&manifest);
+    }
+
+    if let ("search", Some(search_matches)) = matches.subcommand() {
+        let artist_id = search_matches.value_of("artist-id");
+
+        if let Some(artist_id) = artist_id {
+            // Picking a candidate always prompts, so it cannot be scripted.
+            if !scraper.edit_mode.has_prompts() {
+                anyhow::bail!(
+                    "search --artist-id requires a prompt; scrape the listed URL instead"
+                );
+            }
+
+            warn_missing_artist_id(scraper.output_dir, artist_id);
+        }
+
+        let candidates = search(search_matches, scraper.extractor_client.as_ref());
+
+        if artist_id.is_none() && !scraper.edit_mode.has_prompts() {
+            for (i, candidate) in candidates.iter().enumerate() {
+                println!("{}. {}", i + 1, candidate);
+            }
+
+            return Ok(());
+        }
+
+        let candidate = match editor::pick(&candidates) {
+            Some(i) => &candidates[i],
+            None => return Ok(()),
+        };
+
+        let artist_id = match artist_id {
+            Some(artist_id) => artist_id.to_string(),
+            None => {
+                if scraper.output_dir.is_dir() {
+                    editor::set_artist_ids(get_artists(scraper.output_dir));
+                }
+
+                match editor::prompt_artist_id() {
+                    Some(artist_id) => {
+                        warn_missing_artist_id(scraper.output_dir, &artist_id);
+                        artist_id
+                    }
+                    None => return Ok(()),
+                }
+            }
+        };
+
+        let url = Url::parse(&candidate.url)?;
+        return scraper.scrape(&artist_id, &url);
+    }
+
     let artist_id = matches.value_of("artist-id").unwrap();
     let url = value_t!(matches, "url", Url).unwrap_or_else(|e| e.exit());
 
-    if log_enabled!(Level::Warn) && !artist_id_exists(&output_dir, &artist_id) {
+    warn_missing_artist_id(scraper.output_dir, artist_id);
+
+    if extractors::artist_store(&url).is_some() {
+        scraper.crawl(artist_id, &url)
+    } else {
+        scraper.scrape(artist_id, &url)
+    }
+}
+
+fn warn_missing_artist_id(output_dir: &Path, artist_id: &str) {
+    if log_enabled!(Level::Warn) && !artist_id_exists(output_dir, artist_id) {
         warn!("artist id '{}' does not exist", artist_id);
     }
+}
+
+fn parse(matches: &ArgMatches<'_>) -> anyhow::Result<Extraction> {
+    let store = value_t!(matches, "store", Store).unwrap_or_else(|e| e.exit());
+
+    let read = |name| -> anyhow::Result<Option<String>> {
+        match matches.value_of(name) {
+            Some(src) => Ok(Some(fs::read_to_string(src)?)),
+            None => Ok(None),
+        }
+    };
+
+    if store == Store::UpFrontWorks && !matches.is_present("album-id") {
+        clap::Error::with_description(
+            "--album-id <ID> is required to parse an up-front-works page",
+            clap::ErrorKind::MissingRequiredArgument,
+        )
+        .exit();
+    }
+
+    let documents = Documents {
+        album_id: matches.value_of("album-id").map(String::from),
+        html: read("html")?,
+        json: read("json")?,
+    };
+
+    let extraction = extractors::parse(store, &documents)?;
+
+    Ok(extraction)
+}
+
+/// How an extracted album is reviewed before it is written.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum EditMode {
+    /// Prompt to edit the album and its songs.
+    Interactive,
+    /// Edit the album as a document in the user's editor.
+    External,
+    /// Edit the album in a full-screen editor.
+    FullScreen,
+    /// Accept the album without prompts, optionally guessing names.
+    Auto { guess_names: bool },
+}
+
+impl EditMode {
+    /// Returns whether the user is at a terminal to answer prompts, e.g., to pick a candidate.
+    fn has_prompts(self) -> bool {
+        !matches!(self, EditMode::Auto { .. })
+    }
+}
+
+/// The shared state to scrape albums into an output directory.
+struct Scraper<'a> {
+    output_dir: &'a Path,
+    client: Arc<dyn Client>,
+    extractor_client: Arc<dyn Client>,
+    options: extractors::Options,
+    edits: Option<Edits>,
+    edit_mode: EditMode,
+    autosave_dir: PathBuf,
+    report_dst: Option<&'a str>,
+    reports: RefCell<Vec<Report>>,
+}
+
+impl<'a> Scraper<'a> {
+    /// Extracts, edits, and writes the album at the given URL.
+    fn scrape(&self, artist_id: &str, url: &Url) -> anyhow::Result<()> {
+        let extractor = extractors::factory(url, self.extractor_client.clone(), &self.options)?;
+        let extraction = extractor.extract()?;
+
+        self.write(artist_id, extraction)
+    }
+
+    /// Edits an extracted album and writes it with its artwork and, if set, a report.
+    fn write(&self, artist_id: &str, extraction: Extraction) -> anyhow::Result<()> {
+        let Extraction { album, warnings } = extraction;
+
+        let album = match self.edits {
+            Some(ref edits) => editor::apply_edits(&album, edits)?,
+            None => album,
+        };
+
+        let catalog = Catalog::load(self.output_dir, artist_id)?;
+
+        let album = match self.edit_mode {
+            EditMode::Interactive => editor::edit(&album, &catalog, &warnings, &self.autosave_dir),
+            EditMode::External => match editor::edit_external(&album, &catalog, &warnings)? {
+                Some(album) => album,
+                None => anyhow::bail!("edit canceled"),
+            },
+            EditMode::FullScreen => {
+                match editor::edit_full_screen(&album, &catalog, &warnings, artist_id)? {
+                    Some(album) => album,
+                    None => anyhow::bail!("edit canceled"),
+                }
+            }
+            EditMode::Auto { guess_names } => {
+                for warning in &warnings {
+                    warn!("{}", warning);
+                }
+
+                editor::auto_edit(&album, &catalog, guess_names)?
+            }
+        };
+
+        let writer = Writer::new(self.output_dir);
+
+        if album.artwork_url.is_some() {
+            let downloader = Downloader::new(self.client.clone());
+
+            if let Err(e) = writer.write_artwork(&downloader, artist_id, &album) {
+                warn!("failed to download artwork ({:?})", e);
+            }
+        }
+
+        let renderer = Renderer::new();
+        writer.write_templates(&renderer, artist_id, &album)?;
+
+        if self.report_dst.is_some() {
+            self.reports.borrow_mut().push(Report {
+                artist_id: artist_id.into(),
+                album_id: album.id().into(),
+                url: album.url.clone(),
+                warnings,
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Writes the reports of all albums written so far, if a report was requested.
+    fn write_report(&self) -> anyhow::Result<()> {
+        match self.report_dst {
+            Some(dst) => write_report(dst, &self.reports.borrow()),
+            None => Ok(()),
+        }
+    }
+
+    /// Scrapes all albums on an artist page.
+    ///
+    /// Albums that already exist for the artist in the output directory, by URL or original name,
+    /// are skipped. An album that fails to scrape is reported, and the crawl continues.
+    fn crawl(&self, artist_id: &str, url: &Url) -> anyhow::Result<()> {
+        // Edits refer to songs by position, so they only apply to a single album.
+        if self.edits.is_some() {
+            anyhow::bail!("--edits cannot be used with an artist page");
+        }
+
+        let candidates = extractors::discography(url, self.extractor_client.as_ref())?;
+
+        let total = candidates.len();
+        let mut failures = 0;
+
+        for (i, candidate) in candidates.iter().enumerate() {
+            // The catalog is reloaded for each album to include the albums written earlier in the
+            // crawl.
+            let catalog = Catalog::load(self.output_dir, artist_id)?;
+
+            if let Some(entry) = catalog.find_album(&candidate.url, &candidate.name) {
+                println!(
+                    "[{}/{}] skipping {} (exists as '{}')",
+                    i + 1,
+                    total,
+                    candidate,
+                    entry.id
+                );
+
+                continue;
+            }
+
+            println!("[{}/{}] {}", i + 1, total, candidate);
+
+            let result = Url::parse(&candidate.url)
+                .map_err(anyhow::Error::from)
+                .and_then(|url| self.scrape(artist_id, &url));
+
+            if let Err(e) = result {
+                report(&e);
+                failures += 1;
+            }
+        }
+
+        if failures > 0 {
+            anyhow::bail!("failed to scrape {} of {} albums", failures, total);
+        }
+
+        Ok(())
+    }
+
+    /// Scrapes all entries of a manifest and prints a summary of the successes and failures.
+    ///
+    /// Entries are scraped in order. An entry that fails is reported, and the batch continues.
+    fn batch(&self, manifest: &Manifest) -> anyhow::Result<()> {
+        if self.edits.is_some() {
+            anyhow::bail!("--edits cannot be used with batch");
+        }
+
+        let total = manifest.albums.len();
+        let mut outcomes = Vec::with_capacity(total);
+
+        for (i, entry) in manifest.albums.iter().enumerate() {
+            println!("[{}/{}] {} {}", i + 1, total, entry.artist_id, entry.url);
+
+            warn_missing_artist_id(self.output_dir, &entry.artist_id);
+
+            let result = Url::parse(&entry.url)
+                .map_err(anyhow::Error::from)
+                .and_then(|url| {
+                    if extractors::artist_store(&url).is_some() {
+                        self.crawl(&entry.artist_id, &url)
+                    } else {
+                        self.scrape(&entry.artist_id, &url)
+                    }
+                });
+
+            if let Err(ref e) = result {
+                report(e);
+            }
+
+            outcomes.push(Outcome {
+                artist_id: &entry.artist_id,
+                url: &entry.url,
+                error: result.err(),
+            });
+        }
+
+        let failures = outcomes.iter().filter(|o| o.error.is_some()).count();
+
+        println!();
+        println!("{} succeeded, {} failed", total - failures, failures);
+
+        for outcome in &outcomes {
+            if let Some(ref e) = outcome.error {
+                println!("  {} {}: {}", outcome.artist_id, outcome.url, e);
+            }
+        }
+
+        if failures > 0 {
+            anyhow::bail!("failed to scrape {} of {} entries", failures, total);
+        }
+
+        Ok(())
+    }
+}
+
+/// The result of scraping a manifest entry.
+struct Outcome<'a> {
+    artist_id: &'a str,
+    url: &'a str,
+    error: Option<anyhow::Error>,
+}
 
-    let extractor = extractors::factory(&url)?;
+/// Searches the selected stores, or all stores, for album candidates.
+///
+/// A store that fails to search is skipped with a warning.
+fn search(matches: &ArgMatches<'_>, client: &dyn Client) -> Vec<Candidate> {
+    let query: Vec<&str> = matches.values_of("query").unwrap().collect();
+    let query = query.join(" ");
 
-    let album = extractor.extract()?;
-    let album = editor::edit(&album);
+    let stores: Vec<Store> = match matches.values_of("store") {
+        Some(names) => names.map(|name| name.parse().unwrap()).collect(),
+        None => Store::NAMES
+            .iter()
+            .map(|name| name.parse().unwrap())
+            .collect(),
+    };
 
-    let writer = Writer::new(&output_dir);
+    let mut candidates = Vec::new();
 
-    if album.artwork_url.is_some() {
-        if let Err(e) = writer.write_artwork(&artist_id, &album) {
-            warn!("failed to download artwork ({:?})", e);
+    for store in stores {
+        match extractors::search(store, client, &query) {
+            Ok(store_candidates) => candidates.extend(store_candidates),
+            Err(e) => warn!("failed to search {} ({})", store, e),
         }
     }
 
-    let renderer = Renderer::new();
-    writer.write_templates(&renderer, &artist_id, &album)?;
+    candidates
+}
+
+/// A summary of a written album, including the warnings of its extraction.
+#[derive(Serialize)]
+#[serde(rename_all = "kebab-case")]
+struct Report {
+    artist_id: String,
+    album_id: String,
+    url: String,
+    warnings: Vec<Warning>,
+}
+
+/// Writes reports as a JSON array to a file or, if the path is `-`, stdout.
+fn write_report(dst: &str, reports: &[Report]) -> anyhow::Result<()> {
+    if dst == "-" {
+        let stdout = io::stdout();
+        serde_json::to_writer_pretty(stdout.lock(), reports)?;
+        println!();
+    } else {
+        let file = File::create(dst)?;
+        serde_json::to_writer_pretty(file, reports)?;
+    }
 
     Ok(())
 }
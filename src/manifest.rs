@@ -0,0 +1,135 @@
+use std::{fs, io, path::Path};
+
+use serde::Deserialize;
+
+/// A list of albums to scrape, read from a TOML file or a plain list.
+///
+/// The TOML format is a list of entries:
+///
+/// ```toml
+/// [[albums]]
+/// artist-id = "loona/chuu"
+/// url = "https://www.melon.com/album/detail.htm?albumId=10123637"
+/// ```
+///
+/// The plain format is one entry per line, i.e., an artist ID and URL separated by whitespace.
+/// Blank lines and lines starting with `#` are ignored.
+#[derive(Debug, Default, Deserialize, Eq, PartialEq)]
+pub struct Manifest {
+    #[serde(default)]
+    pub albums: Vec<Entry>,
+}
+
+#[derive(Debug, Deserialize, Eq, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub struct Entry {
+    pub artist_id: String,
+    pub url: String,
+}
+
+impl Manifest {
+    /// Reads a manifest from a file.
+    ///
+    /// Files with a `.toml` extension are read as TOML, and all others as plain lists.
+    pub fn load<P>(src: P) -> io::Result<Manifest>
+    where
+        P: AsRef<Path>,
+    {
+        let src = src.as_ref();
+        let data = fs::read_to_string(src)?;
+
+        if src.extension().map(|ext| ext == "toml").unwrap_or(false) {
+            Manifest::from_toml(&data)
+        } else {
+            Manifest::from_lines(&data)
+        }
+    }
+
+    pub fn from_toml(s: &str) -> io::Result<Manifest> {
+        toml::from_str(s).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+
+    pub fn from_lines(s: &str) -> io::Result<Manifest> {
+        let mut albums = Vec::new();
+
+        for (i, line) in s.lines().enumerate() {
+            let line = line.trim();
+
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let fields: Vec<&str> = line.split_whitespace().collect();
+
+            match fields.as_slice() {
+                [artist_id, url] => albums.push(Entry {
+                    artist_id: artist_id.to_string(),
+                    url: url.to_string(),
+                }),
+                _ => {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!("line {}: expected <artist-id> <url>", i + 1),
+                    ))
+                }
+            }
+        }
+
+        Ok(Manifest { albums })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_toml() {
+        let manifest = Manifest::from_toml(
+            r#"
+[[albums]]
+artist-id = "loona/chuu"
+url = "https://www.melon.com/album/detail.htm?albumId=10123637"
+
+[[albums]]
+artist-id = "loona"
+url = "https://www.melon.com/album/detail.htm?albumId=10141232"
+"#,
+        )
+        .unwrap();
+
+        assert_eq!(manifest.albums.len(), 2);
+        assert_eq!(
+            manifest.albums[0],
+            Entry {
+                artist_id: String::from("loona/chuu"),
+                url: String::from("https://www.melon.com/album/detail.htm?albumId=10123637"),
+            }
+        );
+
+        assert!(Manifest::from_toml("").unwrap().albums.is_empty());
+        assert!(Manifest::from_toml("[[albums]]\nurl = \"x\"").is_err());
+    }
+
+    #[test]
+    fn test_from_lines() {
+        let manifest = Manifest::from_lines(
+            "# LOONA\n\
+             loona/chuu  https://www.melon.com/album/detail.htm?albumId=10123637\n\
+             \n\
+             loona\thttps://www.melon.com/album/detail.htm?albumId=10141232\n",
+        )
+        .unwrap();
+
+        assert_eq!(manifest.albums.len(), 2);
+        assert_eq!(manifest.albums[1].artist_id, "loona");
+        assert_eq!(
+            manifest.albums[1].url,
+            "https://www.melon.com/album/detail.htm?albumId=10141232"
+        );
+
+        let e = Manifest::from_lines("loona\n").unwrap_err();
+        assert_eq!(e.kind(), io::ErrorKind::InvalidData);
+        assert_eq!(e.to_string(), "line 1: expected <artist-id> <url>");
+    }
+}
@@ -1,5 +1,6 @@
 pub mod album;
+pub mod lyrics;
 pub mod name;
 pub mod song;
 
-pub use self::{album::Album, name::Name, song::Song};
+pub use self::{album::Album, lyrics::Lyrics, name::Name, song::Song};
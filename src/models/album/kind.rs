@@ -1,8 +1,8 @@
-use std::fmt;
+use std::{fmt, str::FromStr};
 
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 
-#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize)]
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
 #[serde(rename_all = "lowercase")]
 pub enum Kind {
     Single,
@@ -20,6 +20,19 @@ impl fmt::Display for Kind {
     }
 }
 
+impl FromStr for Kind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "single" => Ok(Kind::Single),
+            "ep" => Ok(Kind::Ep),
+            "lp" => Ok(Kind::Lp),
+            _ => Err(format!("invalid album kind: {}", s)),
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -30,4 +43,16 @@ mod tests {
         assert_eq!(Kind::Ep.to_string(), "ep");
         assert_eq!(Kind::Lp.to_string(), "lp");
     }
+
+    #[test]
+    fn test_from_str() {
+        assert_eq!("single".parse(), Ok(Kind::Single));
+        assert_eq!("ep".parse(), Ok(Kind::Ep));
+        assert_eq!("lp".parse(), Ok(Kind::Lp));
+
+        assert_eq!(
+            "album".parse::<Kind>(),
+            Err(String::from("invalid album kind: album"))
+        );
+    }
 }
@@ -0,0 +1,20 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct Lyrics {
+    pub text: String,
+    pub locale: String,
+}
+
+impl Lyrics {
+    pub fn new<S, T>(text: S, locale: T) -> Lyrics
+    where
+        S: Into<String>,
+        T: Into<String>,
+    {
+        Lyrics {
+            text: text.into(),
+            locale: locale.into(),
+        }
+    }
+}
@@ -4,7 +4,11 @@ pub use self::builder::Builder;
 
 use serde::Serialize;
 
-use crate::{editor::SongInput, models::Name};
+use crate::{
+    editor::SongInput,
+    models::{Lyrics, Name},
+    util::inflector::Version,
+};
 
 #[derive(Debug, Serialize)]
 pub struct Song {
@@ -14,6 +18,15 @@ pub struct Song {
     pub duration: i32,
 
     pub names: Vec<Name>,
+
+    pub featured_artists: Vec<String>,
+    pub performers: Vec<String>,
+    pub versions: Vec<Version>,
+
+    pub base_song_id: Option<String>,
+    pub is_existing: bool,
+
+    pub lyrics: Option<Lyrics>,
 }
 
 impl Song {
@@ -43,6 +56,15 @@ impl From<SongInput> for Song {
                 .filter(|n| !n.delete)
                 .map(Name::from)
                 .collect(),
+
+            featured_artists: input.featured_artists,
+            performers: input.performers,
+            versions: input.versions,
+
+            base_song_id: input.base_song_id,
+            is_existing: input.is_existing,
+
+            lyrics: input.lyrics,
         }
     }
 }
@@ -76,4 +98,22 @@ mod tests {
             Some(String::from("Kkumkkuneun Maeumeuro"))
         );
     }
+
+    #[test]
+    fn test_build_with_annotated_title() {
+        let song = Builder::new()
+            .set_position(5)
+            .set_duration(224)
+            .add_name(Name::new(
+                "See Saw (Feat. Kim Lip) (Chuu, Go Won)",
+                "en",
+                true,
+                true,
+            ))
+            .build();
+
+        assert_eq!(song.featured_artists, vec!["Kim Lip"]);
+        assert_eq!(song.performers, vec!["Chuu", "Go Won"]);
+        assert!(song.versions.is_empty());
+    }
 }
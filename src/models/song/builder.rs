@@ -1,4 +1,7 @@
-use crate::{models::Name, util::inflector::parameterize};
+use crate::{
+    models::Name,
+    util::inflector::{parameterize, parse_title, Title},
+};
 
 use super::Song;
 
@@ -49,6 +52,15 @@ impl Builder {
             })
             .expect("missing id");
 
+        // Annotations are taken from the original name, which is typically the raw title from
+        // the store.
+        let title = self
+            .names
+            .iter()
+            .find(|n| n.is_original)
+            .map(|n| parse_title(&n.name))
+            .unwrap_or_else(Title::default);
+
         Song {
             id,
 
@@ -56,6 +68,15 @@ impl Builder {
             duration: self.duration.expect("missing duration"),
 
             names: self.names,
+
+            featured_artists: title.featured_artists,
+            performers: title.performers,
+            versions: title.versions,
+
+            base_song_id: None,
+            is_existing: false,
+
+            lyrics: None,
         }
     }
 }
@@ -37,8 +37,8 @@ impl Renderer {
         HBS.render("album", &data).expect("failed to render album")
     }
 
-    pub fn render_song(&self, song: &Song) -> String {
-        let data = json!({ "song": song });
+    pub fn render_song(&self, artist_id: &str, song: &Song) -> String {
+        let data = json!({ "artist_id": artist_id, "song": song });
         HBS.render("song", &data).expect("failed to render song")
     }
 
@@ -139,7 +139,7 @@ mod helpers {
 mod tests {
     use std::fs;
 
-    use crate::models::{album, song, Name};
+    use crate::models::{album, song, Lyrics, Name};
 
     use super::*;
 
@@ -194,11 +194,38 @@ mod tests {
     fn test_render_song() {
         let album = build_album();
         let renderer = Renderer::new();
-        let result = renderer.render_song(&album.songs[0]);
+        let result = renderer.render_song("iu", &album.songs[0]);
         let expected = fs::read_to_string("tests/snapshots/song.toml").unwrap();
         assert_eq!(result, expected);
     }
 
+    #[test]
+    fn test_render_song_with_base_song_id() {
+        let mut song = song::Builder::new()
+            .set_position(5)
+            .set_duration(266)
+            .add_name(Name::new(
+                "잠 못 드는 밤 비는 내리고 (Inst.)",
+                "ko",
+                true,
+                false,
+            ))
+            .add_name(Name::new(
+                "Jam Mot Deuneun Bam Bineun Naerigo (Inst.)",
+                "ko-Latn",
+                false,
+                true,
+            ))
+            .build();
+
+        song.base_song_id = Some(String::from("jam-mot-deuneun-bam-bineun-naerigo"));
+
+        let renderer = Renderer::new();
+        let result = renderer.render_song("iu", &song);
+
+        assert!(result.ends_with("]\nbase-song-id = \"iu/jam-mot-deuneun-bam-bineun-naerigo\"\n"));
+    }
+
     #[test]
     fn test_render_tracklist() {
         let album = build_album();
@@ -208,6 +235,23 @@ mod tests {
         assert_eq!(result, expected);
     }
 
+    #[test]
+    fn test_render_song_with_lyrics() {
+        let mut song = song::Builder::new()
+            .set_position(1)
+            .set_duration(266)
+            .add_name(Name::new("어젯밤 이야기", "ko", true, false))
+            .add_name(Name::new("Eojetbam Iyagi", "ko-Latn", false, true))
+            .build();
+
+        song.lyrics = Some(Lyrics::new("...", "ko"));
+
+        let renderer = Renderer::new();
+        let result = renderer.render_song("iu", &song);
+
+        assert!(result.ends_with("]\nlyrics-locale = \"ko\"\n"));
+    }
+
     #[test]
     fn test_default_name() {
         let data = json!([
@@ -1,6 +1,10 @@
 pub mod http;
 pub mod inflector;
 pub mod jpeg;
+pub mod script;
+
+#[cfg(test)]
+use std::{env, path::PathBuf};
 
 pub fn format_duration(t: i32) -> String {
     let minutes = t / 60;
@@ -8,6 +12,27 @@ pub fn format_duration(t: i32) -> String {
     format!("{}:{:02}", minutes, seconds)
 }
 
+/// Parses a duration in the form of `m:ss`, e.g., "3:27", to seconds.
+pub fn parse_duration(s: &str) -> Option<i32> {
+    let mut pieces = s.trim().splitn(2, ':');
+
+    let minutes: i32 = pieces.next().and_then(|m| m.parse().ok())?;
+    let seconds: i32 = pieces.next().and_then(|s| s.parse().ok())?;
+
+    if minutes < 0 || !(0..60).contains(&seconds) {
+        return None;
+    }
+
+    Some(minutes * 60 + seconds)
+}
+
+/// Returns a unique path in the temporary directory for a test to write to.
+#[cfg(test)]
+pub(crate) fn tmp_dir(name: &str) -> PathBuf {
+    let id = uuid::Uuid::new_v4();
+    env::temp_dir().join(format!("multimeta-{}-{}", name, id.to_hyphenated_ref()))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -20,4 +45,17 @@ mod tests {
         assert_eq!(format_duration(207), "3:27");
         assert_eq!(format_duration(671), "11:11");
     }
+
+    #[test]
+    fn test_parse_duration() {
+        assert_eq!(parse_duration("0:08"), Some(8));
+        assert_eq!(parse_duration("3:27"), Some(207));
+        assert_eq!(parse_duration("11:11"), Some(671));
+        assert_eq!(parse_duration("04:10"), Some(250));
+
+        assert!(parse_duration("").is_none());
+        assert!(parse_duration("207").is_none());
+        assert!(parse_duration("3:60").is_none());
+        assert!(parse_duration("a:bc").is_none());
+    }
 }
@@ -1,7 +1,28 @@
+mod cache_client;
+mod client;
+mod memory_client;
+mod recording;
+mod retry_client;
+mod throttle_client;
+mod ureq_client;
+
+pub use self::{
+    cache_client::CacheClient,
+    client::{Client, Response},
+    memory_client::MemoryClient,
+    recording::{RecordClient, ReplayClient},
+    retry_client::RetryClient,
+    throttle_client::{Limit, Limits, ThrottleClient},
+    ureq_client::{Builder, UreqClient},
+};
+
 use std::{
+    error, fmt,
     fs::File,
     io::{self, BufReader, BufWriter, Read, Write},
     path::Path,
+    sync::Arc,
+    time::Duration,
 };
 
 use log::info;
@@ -12,17 +33,56 @@ const DEFAULT_BUF_SIZE: usize = 8192; // bytes
 #[derive(Debug)]
 pub enum Error {
     Io(io::Error),
-    RequestFailed,
+    /// The connection failed or timed out.
+    Connection(String),
+    /// The request could not be made, e.g., because of an invalid URL or a TLS error.
+    Transport(String),
+    /// The response has a non-successful status code.
+    ///
+    /// `retry_after` is the delay requested by the server, if any, e.g., for 429 (Too Many
+    /// Requests) or 503 (Service Unavailable).
+    Status {
+        code: u16,
+        retry_after: Option<Duration>,
+    },
     EmptyBody,
 }
 
+impl Error {
+    /// Returns whether the request may succeed when made again.
+    ///
+    /// Connection failures, timeouts, server errors, and 429 (Too Many Requests) are transient.
+    pub fn is_transient(&self) -> bool {
+        match self {
+            Self::Connection(_) => true,
+            Self::Status { code, .. } => *code == 429 || *code >= 500,
+            _ => false,
+        }
+    }
+}
+
+impl error::Error for Error {}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(e) => write!(f, "{}", e),
+            Self::Connection(message) | Self::Transport(message) => {
+                write!(f, "request failed: {}", message)
+            }
+            Self::Status { code, .. } => write!(f, "request failed with status {}", code),
+            Self::EmptyBody => f.write_str("empty body"),
+        }
+    }
+}
+
 pub struct Downloader {
-    client: ureq::Agent,
+    client: Arc<dyn Client>,
 }
 
 impl Downloader {
-    pub fn new() -> Downloader {
-        Downloader::default()
+    pub fn new(client: Arc<dyn Client>) -> Downloader {
+        Downloader { client }
     }
 
     pub fn save<P>(&self, url: &str, dst: P) -> Result<u64, Error>
@@ -34,13 +94,9 @@ impl Downloader {
         let file = File::create(dst).map_err(Error::Io)?;
         let mut writer = BufWriter::new(file);
 
-        let response = self
-            .client
-            .get(url)
-            .call()
-            .map_err(|_| Error::RequestFailed)?;
+        let response = self.client.get(url)?;
 
-        let len = read_content_length(&response).ok_or(Error::EmptyBody)?;
+        let len = response.content_length().ok_or(Error::EmptyBody)?;
 
         let mut pb = ProgressBar::new(len);
         pb.set_units(Units::Bytes);
@@ -57,20 +113,6 @@ impl Downloader {
     }
 }
 
-impl Default for Downloader {
-    fn default() -> Downloader {
-        Downloader {
-            client: ureq::agent(),
-        }
-    }
-}
-
-fn read_content_length(response: &ureq::Response) -> Option<u64> {
-    response
-        .header("Content-Length")
-        .and_then(|s| s.parse().ok())
-}
-
 fn copy<R, W, F>(reader: &mut R, writer: &mut W, mut cb: F) -> Result<u64, Error>
 where
     R: Read,
@@ -99,6 +141,26 @@ where
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_save() {
+        let client = MemoryClient::new();
+        client.insert("http://localhost/artwork.jpg", "jpeg");
+
+        let downloader = Downloader::new(Arc::new(client));
+
+        let dst = std::env::temp_dir().join("multimeta-test-save.jpg");
+
+        assert!(downloader.save("http://localhost/404.jpg", &dst).is_err());
+
+        let len = downloader
+            .save("http://localhost/artwork.jpg", &dst)
+            .unwrap();
+        assert_eq!(len, 4);
+        assert_eq!(std::fs::read(&dst).unwrap(), b"jpeg");
+
+        std::fs::remove_file(&dst).unwrap();
+    }
+
     #[test]
     fn test_copy() {
         let mut src: &[u8] = b"hello";
@@ -0,0 +1,167 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::{Duration, SystemTime},
+};
+
+use log::{info, warn};
+use sha2::{Digest, Sha256};
+
+use super::{Client, Error, Response};
+
+/// An HTTP client that caches successful response bodies on disk.
+///
+/// Entries are content addressed by the SHA-256 digest of the request URL. An entry is stale when
+/// its age reaches the time to live (TTL), in which case the request is made again. The content
+/// type of a response is kept with its entry, e.g., for [`RecordClient`](super::RecordClient).
+pub struct CacheClient {
+    inner: Arc<dyn Client>,
+    dir: PathBuf,
+    ttl: Duration,
+    refresh: bool,
+}
+
+impl CacheClient {
+    pub fn new<P>(inner: Arc<dyn Client>, dir: P, ttl: Duration) -> CacheClient
+    where
+        P: AsRef<Path>,
+    {
+        CacheClient {
+            inner,
+            dir: dir.as_ref().into(),
+            ttl,
+            refresh: false,
+        }
+    }
+
+    /// Sets whether to ignore existing entries.
+    ///
+    /// Responses are still written to the cache.
+    pub fn with_refresh(mut self, refresh: bool) -> CacheClient {
+        self.refresh = refresh;
+        self
+    }
+
+    fn entry_path(&self, url: &str) -> PathBuf {
+        self.dir
+            .join(format!("{:x}", Sha256::digest(url.as_bytes())))
+    }
+
+    fn read(&self, url: &str) -> Option<(Vec<u8>, Option<String>)> {
+        let path = self.entry_path(url);
+
+        let age = path
+            .metadata()
+            .and_then(|m| m.modified())
+            .ok()
+            .and_then(|t| SystemTime::now().duration_since(t).ok())?;
+
+        if age >= self.ttl {
+            return None;
+        }
+
+        let body = fs::read(&path).ok()?;
+        let content_type = fs::read_to_string(path.with_extension("type")).ok();
+
+        Some((body, content_type))
+    }
+
+    fn write(&self, url: &str, content_type: Option<&str>, body: &[u8]) -> io::Result<()> {
+        fs::create_dir_all(&self.dir)?;
+
+        let path = self.entry_path(url);
+
+        // Write to a temporary file first so that an interrupted write does not leave a partial
+        // entry.
+        let tmp_path = path.with_extension("tmp");
+        fs::write(&tmp_path, body)?;
+        fs::rename(&tmp_path, &path)?;
+
+        let type_path = path.with_extension("type");
+
+        match content_type {
+            Some(content_type) => fs::write(type_path, content_type)?,
+            None => match fs::remove_file(type_path) {
+                Err(e) if e.kind() != io::ErrorKind::NotFound => return Err(e),
+                _ => {}
+            },
+        }
+
+        fs::write(path.with_extension("url"), url)
+    }
+}
+
+impl Client for CacheClient {
+    fn get(&self, url: &str) -> Result<Response, Error> {
+        if !self.refresh {
+            if let Some((body, content_type)) = self.read(url) {
+                info!("using cached response for {}", url);
+                return Ok(Response::from_bytes_with_content_type(
+                    body,
+                    content_type.as_deref(),
+                ));
+            }
+        }
+
+        let response = self.inner.get(url)?;
+
+        let content_type = response.header("Content-Type").map(String::from);
+        let body = response.into_bytes().map_err(Error::Io)?;
+
+        if let Err(e) = self.write(url, content_type.as_deref(), &body) {
+            warn!("failed to cache response for {} ({})", url, e);
+        }
+
+        Ok(Response::from_bytes_with_content_type(
+            body,
+            content_type.as_deref(),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::util::{http::MemoryClient, tmp_dir};
+
+    use super::*;
+
+    static URL: &str = "http://localhost/albums/1";
+
+    #[test]
+    fn test_get() {
+        let dir = tmp_dir("cache");
+        let ttl = Duration::from_secs(60);
+
+        let inner = MemoryClient::new();
+        inner.insert(URL, "hello");
+
+        let client = CacheClient::new(Arc::new(inner), &dir, ttl);
+        let response = client.get(URL).unwrap();
+        assert_eq!(response.into_string().unwrap(), "hello");
+
+        // The response is served from the cache when the inner client no longer has it.
+        let client = CacheClient::new(Arc::new(MemoryClient::new()), &dir, ttl);
+        let response = client.get(URL).unwrap();
+        assert_eq!(response.into_string().unwrap(), "hello");
+
+        let client = CacheClient::new(Arc::new(MemoryClient::new()), &dir, ttl).with_refresh(true);
+        assert!(client.get(URL).is_err());
+
+        let client = CacheClient::new(Arc::new(MemoryClient::new()), &dir, Duration::from_secs(0));
+        assert!(client.get(URL).is_err());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_entry_path() {
+        let client = CacheClient::new(Arc::new(MemoryClient::new()), "cache", Duration::default());
+
+        assert_eq!(
+            client.entry_path(URL),
+            Path::new("cache")
+                .join("f594574816683ba563fb03ccb4516a25291d7b6b6e3fa77580ebb110e7f3721f")
+        );
+    }
+}
@@ -0,0 +1,130 @@
+use std::{
+    fmt,
+    io::{self, Read},
+};
+
+use super::Error;
+
+/// An HTTP client used to make requests to stores and artwork hosts.
+pub trait Client: Send + Sync {
+    /// Makes a GET request to the given URL.
+    ///
+    /// Responses with a non-successful status code return [`Error::Status`].
+    fn get(&self, url: &str) -> Result<Response, Error>;
+}
+
+/// A response from an HTTP client.
+pub struct Response {
+    status: u16,
+    headers: Vec<(String, String)>,
+    reader: Box<dyn Read + Send>,
+}
+
+impl Response {
+    pub fn new<R>(status: u16, headers: Vec<(String, String)>, reader: R) -> Response
+    where
+        R: Read + Send + 'static,
+    {
+        Response {
+            status,
+            headers,
+            reader: Box::new(reader),
+        }
+    }
+
+    /// Creates a successful response from a buffered body.
+    pub fn from_bytes<B>(body: B) -> Response
+    where
+        B: Into<Vec<u8>>,
+    {
+        let body = body.into();
+        let headers = vec![(String::from("Content-Length"), body.len().to_string())];
+        Response::new(200, headers, io::Cursor::new(body))
+    }
+
+    /// Creates a successful response from a buffered body with its content type, if known.
+    pub fn from_bytes_with_content_type<B>(body: B, content_type: Option<&str>) -> Response
+    where
+        B: Into<Vec<u8>>,
+    {
+        let mut response = Response::from_bytes(body);
+
+        if let Some(content_type) = content_type {
+            response
+                .headers
+                .push((String::from("Content-Type"), content_type.into()));
+        }
+
+        response
+    }
+
+    pub fn status(&self) -> u16 {
+        self.status
+    }
+
+    pub fn headers(&self) -> &[(String, String)] {
+        &self.headers
+    }
+
+    /// Returns the value of the first header with the given name, ignoring case.
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(k, _)| k.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.as_str())
+    }
+
+    pub fn content_length(&self) -> Option<u64> {
+        self.header("Content-Length").and_then(|s| s.parse().ok())
+    }
+
+    pub fn into_reader(self) -> Box<dyn Read + Send> {
+        self.reader
+    }
+
+    pub fn into_bytes(self) -> io::Result<Vec<u8>> {
+        let mut buf = Vec::new();
+        self.into_reader().read_to_end(&mut buf)?;
+        Ok(buf)
+    }
+
+    pub fn into_string(self) -> io::Result<String> {
+        let buf = self.into_bytes()?;
+        String::from_utf8(buf).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+}
+
+impl fmt::Debug for Response {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Response")
+            .field("status", &self.status)
+            .field("headers", &self.headers)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_header() {
+        let response = Response::new(
+            200,
+            vec![(String::from("Content-Type"), String::from("text/html"))],
+            io::empty(),
+        );
+
+        assert_eq!(response.header("Content-Type"), Some("text/html"));
+        assert_eq!(response.header("content-type"), Some("text/html"));
+        assert!(response.header("Content-Length").is_none());
+    }
+
+    #[test]
+    fn test_from_bytes() {
+        let response = Response::from_bytes("hello");
+        assert_eq!(response.status(), 200);
+        assert_eq!(response.content_length(), Some(5));
+        assert_eq!(response.into_string().unwrap(), "hello");
+    }
+}
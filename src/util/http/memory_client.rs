@@ -0,0 +1,68 @@
+use std::{collections::HashMap, fs, io, path::Path, sync::RwLock};
+
+use super::{Client, Error, Response};
+
+/// An HTTP client that serves responses from memory.
+///
+/// This is useful to run extractors on fixtures without a network. Requests to unknown URLs
+/// return a 404 status error.
+#[derive(Default)]
+pub struct MemoryClient {
+    bodies: RwLock<HashMap<String, Vec<u8>>>,
+}
+
+impl MemoryClient {
+    pub fn new() -> MemoryClient {
+        MemoryClient::default()
+    }
+
+    pub fn insert<U, B>(&self, url: U, body: B)
+    where
+        U: Into<String>,
+        B: Into<Vec<u8>>,
+    {
+        let mut bodies = self.bodies.write().unwrap();
+        bodies.insert(url.into(), body.into());
+    }
+
+    /// Serves the contents of a file as the body for the given URL.
+    pub fn insert_file<U, P>(&self, url: U, src: P) -> io::Result<()>
+    where
+        U: Into<String>,
+        P: AsRef<Path>,
+    {
+        let body = fs::read(src)?;
+        self.insert(url, body);
+        Ok(())
+    }
+}
+
+impl Client for MemoryClient {
+    fn get(&self, url: &str) -> Result<Response, Error> {
+        let bodies = self.bodies.read().unwrap();
+
+        bodies
+            .get(url)
+            .map(|body| Response::from_bytes(body.clone()))
+            .ok_or(Error::Status {
+                code: 404,
+                retry_after: None,
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_get() {
+        let client = MemoryClient::new();
+        client.insert("http://localhost/", "hello");
+
+        let response = client.get("http://localhost/").unwrap();
+        assert_eq!(response.into_string().unwrap(), "hello");
+
+        assert!(client.get("http://localhost/404").is_err());
+    }
+}
@@ -0,0 +1,238 @@
+use std::{
+    fs,
+    io::{self, BufReader},
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
+
+use serde::{Deserialize, Serialize};
+
+use super::{Client, Error, MemoryClient, Response};
+
+static INDEX_FILENAME: &str = "index.json";
+
+#[derive(Debug, Default, Deserialize, Serialize)]
+struct Index {
+    entries: Vec<Entry>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+struct Entry {
+    url: String,
+    path: String,
+}
+
+impl Index {
+    fn read(dir: &Path) -> io::Result<Index> {
+        let file = fs::File::open(dir.join(INDEX_FILENAME))?;
+        let reader = BufReader::new(file);
+        serde_json::from_reader(reader).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+
+    fn write(&self, dir: &Path) -> io::Result<()> {
+        let data = serde_json::to_vec_pretty(self)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        fs::write(dir.join(INDEX_FILENAME), data)
+    }
+}
+
+/// An HTTP client that saves every successful response it makes to a directory.
+///
+/// Each response body is written to its own file, and the URL of each file is listed in the
+/// directory's index (`index.json`). The directory can be served back using [`ReplayClient`].
+pub struct RecordClient {
+    inner: Arc<dyn Client>,
+    dir: PathBuf,
+    index: Mutex<Index>,
+}
+
+impl RecordClient {
+    pub fn new<P>(inner: Arc<dyn Client>, dir: P) -> io::Result<RecordClient>
+    where
+        P: AsRef<Path>,
+    {
+        let dir = dir.as_ref();
+
+        fs::create_dir_all(dir)?;
+
+        // Recordings can be added to, e.g., to combine multiple runs.
+        let index = if dir.join(INDEX_FILENAME).exists() {
+            Index::read(dir)?
+        } else {
+            Index::default()
+        };
+
+        Ok(RecordClient {
+            inner,
+            dir: dir.into(),
+            index: Mutex::new(index),
+        })
+    }
+
+    fn record(&self, url: &str, content_type: Option<&str>, body: &[u8]) -> io::Result<()> {
+        let mut index = self.index.lock().unwrap();
+
+        let path = match index.entries.iter().find(|e| e.url == url) {
+            Some(entry) => entry.path.clone(),
+            None => {
+                let path = format!(
+                    "{:04}.{}",
+                    index.entries.len() + 1,
+                    guess_extension(content_type)
+                );
+
+                index.entries.push(Entry {
+                    url: url.into(),
+                    path: path.clone(),
+                });
+
+                path
+            }
+        };
+
+        fs::write(self.dir.join(path), body)?;
+        index.write(&self.dir)
+    }
+}
+
+impl Client for RecordClient {
+    fn get(&self, url: &str) -> Result<Response, Error> {
+        let response = self.inner.get(url)?;
+
+        let content_type = response.header("Content-Type").map(String::from);
+        let body = response.into_bytes().map_err(Error::Io)?;
+
+        self.record(url, content_type.as_deref(), &body)
+            .map_err(Error::Io)?;
+
+        Ok(Response::from_bytes_with_content_type(
+            body,
+            content_type.as_deref(),
+        ))
+    }
+}
+
+/// An HTTP client that serves responses saved by [`RecordClient`].
+///
+/// Requests to URLs that are not in the recording return a 404 status error.
+pub struct ReplayClient {
+    inner: MemoryClient,
+}
+
+impl ReplayClient {
+    pub fn open<P>(dir: P) -> io::Result<ReplayClient>
+    where
+        P: AsRef<Path>,
+    {
+        let dir = dir.as_ref();
+        let index = Index::read(dir)?;
+
+        let inner = MemoryClient::new();
+
+        for entry in index.entries {
+            inner.insert_file(entry.url, dir.join(entry.path))?;
+        }
+
+        Ok(ReplayClient { inner })
+    }
+}
+
+impl Client for ReplayClient {
+    fn get(&self, url: &str) -> Result<Response, Error> {
+        self.inner.get(url)
+    }
+}
+
+fn guess_extension(content_type: Option<&str>) -> &'static str {
+    match content_type {
+        Some(t) if t.contains("html") => "html",
+        Some(t) if t.contains("json") => "json",
+        Some(t) if t.contains("jpeg") => "jpg",
+        _ => "bin",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::util::{http::CacheClient, tmp_dir};
+
+    use super::*;
+
+    // A client that serves a body with a content type, like a store.
+    struct HtmlClient;
+
+    impl Client for HtmlClient {
+        fn get(&self, _: &str) -> Result<Response, Error> {
+            Ok(Response::from_bytes_with_content_type(
+                "<html />",
+                Some("text/html; charset=UTF-8"),
+            ))
+        }
+    }
+
+    #[test]
+    fn test_record_and_replay() {
+        let dir = tmp_dir("recording");
+
+        let inner = MemoryClient::new();
+        inner.insert("http://localhost/albums/1", "<html />");
+        inner.insert("http://localhost/albums/1.json", "{}");
+
+        let client = RecordClient::new(Arc::new(inner), &dir).unwrap();
+        client.get("http://localhost/albums/1").unwrap();
+        client.get("http://localhost/albums/1.json").unwrap();
+        assert!(client.get("http://localhost/albums/2").is_err());
+
+        let client = ReplayClient::open(&dir).unwrap();
+
+        let response = client.get("http://localhost/albums/1").unwrap();
+        assert_eq!(response.into_string().unwrap(), "<html />");
+
+        let response = client.get("http://localhost/albums/1.json").unwrap();
+        assert_eq!(response.into_string().unwrap(), "{}");
+
+        assert!(client.get("http://localhost/albums/2").is_err());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_record_through_cache() {
+        let cache_dir = tmp_dir("cache");
+        let ttl = Duration::from_secs(60);
+
+        // The first recording is a cache miss, and the second is a cache hit, since the inner
+        // client of the second no longer serves the URL.
+        let inners: [Arc<dyn Client>; 2] = [Arc::new(HtmlClient), Arc::new(MemoryClient::new())];
+
+        for inner in inners.iter() {
+            let dir = tmp_dir("recording");
+
+            let cache_client = CacheClient::new(inner.clone(), &cache_dir, ttl);
+            let client = RecordClient::new(Arc::new(cache_client), &dir).unwrap();
+
+            let response = client.get("http://localhost/albums/1").unwrap();
+            assert_eq!(
+                response.header("Content-Type"),
+                Some("text/html; charset=UTF-8")
+            );
+
+            let index = Index::read(&dir).unwrap();
+            assert_eq!(index.entries[0].path, "0001.html");
+
+            fs::remove_dir_all(&dir).unwrap();
+        }
+
+        fs::remove_dir_all(&cache_dir).unwrap();
+    }
+
+    #[test]
+    fn test_guess_extension() {
+        assert_eq!(guess_extension(Some("text/html; charset=UTF-8")), "html");
+        assert_eq!(guess_extension(Some("application/json")), "json");
+        assert_eq!(guess_extension(Some("image/jpeg")), "jpg");
+        assert_eq!(guess_extension(None), "bin");
+    }
+}
@@ -0,0 +1,298 @@
+use std::{cmp, sync::Arc, thread, time::Duration};
+
+use chrono::{DateTime, Utc};
+use log::warn;
+
+use super::{Client, Error, Response};
+
+const DEFAULT_MAX_RETRIES: u32 = 3;
+const DEFAULT_BASE_DELAY: Duration = Duration::from_secs(1);
+const DEFAULT_MAX_DELAY: Duration = Duration::from_secs(60);
+
+/// An HTTP client that retries requests that fail with a transient error.
+///
+/// The delay between attempts doubles after each retry, starting at the base delay and capped
+/// at the max delay. A `Retry-After` sent by the server is used instead, unless it exceeds the max
+/// delay, in which case the request is not retried.
+pub struct RetryClient {
+    inner: Arc<dyn Client>,
+    max_retries: u32,
+    base_delay: Duration,
+    max_delay: Duration,
+}
+
+impl RetryClient {
+    pub fn new(inner: Arc<dyn Client>) -> RetryClient {
+        RetryClient {
+            inner,
+            max_retries: DEFAULT_MAX_RETRIES,
+            base_delay: DEFAULT_BASE_DELAY,
+            max_delay: DEFAULT_MAX_DELAY,
+        }
+    }
+
+    /// Sets the number of times a request is made again after the first attempt.
+    pub fn with_max_retries(mut self, max_retries: u32) -> RetryClient {
+        self.max_retries = max_retries;
+        self
+    }
+
+    pub fn with_base_delay(mut self, base_delay: Duration) -> RetryClient {
+        self.base_delay = base_delay;
+        self
+    }
+
+    pub fn with_max_delay(mut self, max_delay: Duration) -> RetryClient {
+        self.max_delay = max_delay;
+        self
+    }
+
+    fn delay(&self, error: &Error, retry: u32) -> Option<Duration> {
+        if let Error::Status {
+            retry_after: Some(retry_after),
+            ..
+        } = error
+        {
+            if *retry_after > self.max_delay {
+                return None;
+            }
+
+            return Some(*retry_after);
+        }
+
+        let delay = self
+            .base_delay
+            .checked_mul(1 << cmp::min(retry, 16))
+            .unwrap_or(self.max_delay);
+
+        Some(cmp::min(delay, self.max_delay))
+    }
+}
+
+impl Client for RetryClient {
+    fn get(&self, url: &str) -> Result<Response, Error> {
+        let mut retry = 0;
+
+        loop {
+            let error = match self.inner.get(url) {
+                Ok(response) => return Ok(response),
+                Err(e) => e,
+            };
+
+            if retry >= self.max_retries || !error.is_transient() {
+                return Err(error);
+            }
+
+            let delay = match self.delay(&error, retry) {
+                Some(delay) => delay,
+                None => return Err(error),
+            };
+
+            warn!(
+                "request to {} failed ({}); retrying in {:?} ({}/{})",
+                url,
+                error,
+                delay,
+                retry + 1,
+                self.max_retries
+            );
+
+            thread::sleep(delay);
+
+            retry += 1;
+        }
+    }
+}
+
+/// Parses the value of a `Retry-After` header.
+///
+/// The value is either a number of seconds or an HTTP date. Dates in the past are a zero delay.
+pub(super) fn parse_retry_after(s: &str) -> Option<Duration> {
+    let s = s.trim();
+
+    if let Ok(secs) = s.parse() {
+        return Some(Duration::from_secs(secs));
+    }
+
+    let date = DateTime::parse_from_rfc2822(s).ok()?;
+    let delay = date.with_timezone(&Utc) - Utc::now();
+
+    Some(delay.to_std().unwrap_or_default())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        io::{BufRead, BufReader, Write},
+        net::TcpListener,
+    };
+
+    use crate::util::http::UreqClient;
+
+    use super::*;
+
+    static OK: &str = "HTTP/1.1 200 OK\r\nContent-Length: 2\r\nConnection: close\r\n\r\nok";
+    static INTERNAL_SERVER_ERROR: &str =
+        "HTTP/1.1 500 Internal Server Error\r\nContent-Length: 0\r\nConnection: close\r\n\r\n";
+    static NOT_FOUND: &str =
+        "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n";
+    static TOO_MANY_REQUESTS: &str =
+        "HTTP/1.1 429 Too Many Requests\r\nRetry-After: 0\r\nContent-Length: 0\r\nConnection: close\r\n\r\n";
+    static SERVICE_UNAVAILABLE: &str =
+        "HTTP/1.1 503 Service Unavailable\r\nRetry-After: 3600\r\nContent-Length: 0\r\nConnection: close\r\n\r\n";
+
+    // Starts a local server that sends the given responses, in order, one per connection. A
+    // `None` response reads the request but never responds.
+    fn serve(responses: Vec<Option<&'static str>>) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        thread::spawn(move || {
+            for (stream, response) in listener.incoming().zip(responses) {
+                let mut stream = stream.unwrap();
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+
+                let mut line = String::new();
+
+                while reader.read_line(&mut line).unwrap() > 0 && line != "\r\n" {
+                    line.clear();
+                }
+
+                match response {
+                    Some(response) => stream.write_all(response.as_bytes()).unwrap(),
+                    None => {
+                        thread::spawn(move || {
+                            thread::sleep(Duration::from_secs(1));
+                            drop(stream);
+                        });
+                    }
+                }
+            }
+        });
+
+        format!("http://{}/", addr)
+    }
+
+    fn build_client(timeout: Duration) -> RetryClient {
+        let inner = UreqClient::builder().set_timeout(timeout).build().unwrap();
+
+        RetryClient::new(Arc::new(inner))
+            .with_max_retries(2)
+            .with_base_delay(Duration::from_millis(1))
+    }
+
+    #[test]
+    fn test_get() {
+        let client = build_client(Duration::from_secs(5));
+
+        let url = serve(vec![Some(INTERNAL_SERVER_ERROR), Some(OK)]);
+        let response = client.get(&url).unwrap();
+        assert_eq!(response.into_string().unwrap(), "ok");
+
+        let url = serve(vec![Some(TOO_MANY_REQUESTS), Some(OK)]);
+        assert!(client.get(&url).is_ok());
+
+        let url = serve(vec![
+            Some(INTERNAL_SERVER_ERROR),
+            Some(INTERNAL_SERVER_ERROR),
+            Some(INTERNAL_SERVER_ERROR),
+            Some(OK),
+        ]);
+
+        match client.get(&url) {
+            Err(Error::Status { code: 500, .. }) => {}
+            result => panic!("expected status 500, got {:?}", result),
+        }
+    }
+
+    #[test]
+    fn test_get_with_non_transient_error() {
+        let client = build_client(Duration::from_secs(5));
+        let url = serve(vec![Some(NOT_FOUND), Some(OK)]);
+
+        match client.get(&url) {
+            Err(Error::Status { code: 404, .. }) => {}
+            result => panic!("expected status 404, got {:?}", result),
+        }
+    }
+
+    #[test]
+    fn test_get_with_transport_error() {
+        let client = build_client(Duration::from_secs(5));
+
+        match client.get("ftp://127.0.0.1/") {
+            Err(Error::Transport(_)) => {}
+            result => panic!("expected transport error, got {:?}", result),
+        }
+
+        // A closed port refuses the connection.
+        let addr = TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap();
+
+        match client.get(&format!("http://{}/", addr)) {
+            Err(Error::Connection(_)) => {}
+            result => panic!("expected connection error, got {:?}", result),
+        }
+    }
+
+    #[test]
+    fn test_get_with_retry_after_exceeding_max_delay() {
+        let client = build_client(Duration::from_secs(5));
+        let url = serve(vec![Some(SERVICE_UNAVAILABLE), Some(OK)]);
+
+        match client.get(&url) {
+            Err(Error::Status {
+                code: 503,
+                retry_after,
+            }) => assert_eq!(retry_after, Some(Duration::from_secs(3600))),
+            result => panic!("expected status 503, got {:?}", result),
+        }
+    }
+
+    #[test]
+    fn test_get_with_timeout() {
+        let client = build_client(Duration::from_millis(100));
+        let url = serve(vec![None, Some(OK)]);
+        assert!(client.get(&url).is_ok());
+    }
+
+    #[test]
+    fn test_delay() {
+        let client = RetryClient::new(Arc::new(UreqClient::new()))
+            .with_base_delay(Duration::from_secs(1))
+            .with_max_delay(Duration::from_secs(5));
+
+        let error = Error::Connection(String::from("connection refused"));
+        assert_eq!(client.delay(&error, 0), Some(Duration::from_secs(1)));
+        assert_eq!(client.delay(&error, 1), Some(Duration::from_secs(2)));
+        assert_eq!(client.delay(&error, 2), Some(Duration::from_secs(4)));
+        assert_eq!(client.delay(&error, 3), Some(Duration::from_secs(5)));
+        assert_eq!(client.delay(&error, 64), Some(Duration::from_secs(5)));
+
+        let error = Error::Status {
+            code: 503,
+            retry_after: Some(Duration::from_secs(3)),
+        };
+        assert_eq!(client.delay(&error, 0), Some(Duration::from_secs(3)));
+
+        let error = Error::Status {
+            code: 503,
+            retry_after: Some(Duration::from_secs(8)),
+        };
+        assert_eq!(client.delay(&error, 0), None);
+    }
+
+    #[test]
+    fn test_parse_retry_after() {
+        assert_eq!(parse_retry_after("120"), Some(Duration::from_secs(120)));
+        assert_eq!(parse_retry_after(" 0 "), Some(Duration::from_secs(0)));
+        assert_eq!(
+            parse_retry_after("Wed, 21 Oct 2015 07:28:00 GMT"),
+            Some(Duration::from_secs(0))
+        );
+        assert!(parse_retry_after("soon").is_none());
+        assert!(parse_retry_after("-1").is_none());
+    }
+}
@@ -0,0 +1,238 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Condvar, Mutex},
+    thread,
+    time::{Duration, Instant},
+};
+
+use log::info;
+use url::Url;
+
+use super::{Client, Error, Response};
+
+/// The request limits of a host.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Limit {
+    /// The minimum time between the start of two requests.
+    pub interval: Duration,
+    /// The maximum number of requests waiting for a response at the same time.
+    pub max_concurrent_requests: usize,
+}
+
+impl Default for Limit {
+    fn default() -> Limit {
+        Limit {
+            interval: Duration::from_secs(1),
+            max_concurrent_requests: 2,
+        }
+    }
+}
+
+/// The request limits of all hosts.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Limits {
+    pub default: Limit,
+    pub hosts: HashMap<String, Limit>,
+}
+
+impl Limits {
+    pub fn get(&self, host: &str) -> Limit {
+        self.hosts.get(host).copied().unwrap_or(self.default)
+    }
+}
+
+#[derive(Default)]
+struct HostState {
+    active: usize,
+    next_request_at: Option<Instant>,
+}
+
+/// An HTTP client that limits the rate and concurrency of requests per host.
+///
+/// A request counts toward the concurrency limit until its response headers are received.
+pub struct ThrottleClient {
+    inner: Arc<dyn Client>,
+    limits: Limits,
+    hosts: Mutex<HashMap<String, HostState>>,
+    released: Condvar,
+}
+
+impl ThrottleClient {
+    pub fn new(inner: Arc<dyn Client>, limits: Limits) -> ThrottleClient {
+        ThrottleClient {
+            inner,
+            limits,
+            hosts: Mutex::new(HashMap::new()),
+            released: Condvar::new(),
+        }
+    }
+
+    // Waits for a request slot of the host and returns when the request can start.
+    fn acquire(&self, host: &str) -> Instant {
+        let limit = self.limits.get(host);
+        let mut hosts = self.hosts.lock().unwrap();
+
+        loop {
+            let state = hosts.entry(host.into()).or_default();
+
+            if state.active < limit.max_concurrent_requests.max(1) {
+                let now = Instant::now();
+
+                let start = match state.next_request_at {
+                    Some(next_request_at) if next_request_at > now => next_request_at,
+                    _ => now,
+                };
+
+                state.active += 1;
+                state.next_request_at = Some(start + limit.interval);
+
+                return start;
+            }
+
+            hosts = self.released.wait(hosts).unwrap();
+        }
+    }
+
+    fn release(&self, host: &str) {
+        let mut hosts = self.hosts.lock().unwrap();
+
+        if let Some(state) = hosts.get_mut(host) {
+            state.active -= 1;
+        }
+
+        self.released.notify_all();
+    }
+}
+
+impl Client for ThrottleClient {
+    fn get(&self, url: &str) -> Result<Response, Error> {
+        let host = match Url::parse(url)
+            .ok()
+            .and_then(|u| u.host_str().map(String::from))
+        {
+            Some(host) => host,
+            None => return self.inner.get(url),
+        };
+
+        let start = self.acquire(&host);
+        let _slot = Slot {
+            client: self,
+            host: &host,
+        };
+
+        let now = Instant::now();
+
+        if start > now {
+            let delay = start - now;
+            info!("waiting {:?} before requesting {}", delay, url);
+            thread::sleep(delay);
+        }
+
+        self.inner.get(url)
+    }
+}
+
+// Releases a request slot when dropped.
+struct Slot<'a> {
+    client: &'a ThrottleClient,
+    host: &'a str,
+}
+
+impl<'a> Drop for Slot<'a> {
+    fn drop(&mut self) {
+        self.client.release(self.host);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::util::http::MemoryClient;
+
+    use super::*;
+
+    fn build_client(limit: Limit) -> ThrottleClient {
+        let inner = MemoryClient::new();
+        inner.insert("http://localhost/1", "1");
+        inner.insert("http://localhost/2", "2");
+        inner.insert("http://127.0.0.1/1", "1");
+
+        let limits = Limits {
+            default: Limit {
+                interval: Duration::from_secs(0),
+                max_concurrent_requests: 1,
+            },
+            hosts: vec![(String::from("localhost"), limit)]
+                .into_iter()
+                .collect(),
+        };
+
+        ThrottleClient::new(Arc::new(inner), limits)
+    }
+
+    #[test]
+    fn test_get() {
+        let interval = Duration::from_millis(50);
+
+        let client = build_client(Limit {
+            interval,
+            max_concurrent_requests: 1,
+        });
+
+        let start = Instant::now();
+
+        client.get("http://localhost/1").unwrap();
+        client.get("http://127.0.0.1/1").unwrap();
+        assert!(start.elapsed() < interval);
+
+        client.get("http://localhost/2").unwrap();
+        assert!(start.elapsed() >= interval);
+
+        assert!(client.get("http://localhost/404").is_err());
+        assert!(start.elapsed() >= interval * 2);
+    }
+
+    #[test]
+    fn test_acquire() {
+        let client = Arc::new(build_client(Limit {
+            interval: Duration::from_secs(0),
+            max_concurrent_requests: 1,
+        }));
+
+        client.acquire("localhost");
+
+        let handle = {
+            let client = client.clone();
+
+            thread::spawn(move || {
+                client.acquire("localhost");
+                client.release("localhost");
+            })
+        };
+
+        thread::sleep(Duration::from_millis(50));
+        assert_eq!(client.hosts.lock().unwrap()["localhost"].active, 1);
+
+        client.release("localhost");
+        handle.join().unwrap();
+
+        assert_eq!(client.hosts.lock().unwrap()["localhost"].active, 0);
+    }
+
+    #[test]
+    fn test_limits_get() {
+        let limit = Limit {
+            interval: Duration::from_secs(2),
+            max_concurrent_requests: 1,
+        };
+
+        let limits = Limits {
+            default: Limit::default(),
+            hosts: vec![(String::from("www.melon.com"), limit)]
+                .into_iter()
+                .collect(),
+        };
+
+        assert_eq!(limits.get("www.melon.com"), limit);
+        assert_eq!(limits.get("mora.jp"), Limit::default());
+    }
+}
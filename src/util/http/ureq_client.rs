@@ -0,0 +1,160 @@
+use std::{error::Error as _, io, time::Duration};
+
+use super::{retry_client::parse_retry_after, Client, Error, Response};
+
+static USER_AGENT: &str = concat!(env!("CARGO_PKG_NAME"), "/", env!("CARGO_PKG_VERSION"));
+
+/// An HTTP client backed by `ureq`.
+///
+/// Every request sends a user agent, which is required by some stores, e.g., mora.
+pub struct UreqClient {
+    agent: ureq::Agent,
+    headers: Vec<(String, String)>,
+}
+
+impl UreqClient {
+    pub fn new() -> UreqClient {
+        UreqClient::default()
+    }
+
+    pub fn builder() -> Builder {
+        Builder::default()
+    }
+}
+
+impl Default for UreqClient {
+    fn default() -> UreqClient {
+        Builder::default()
+            .build()
+            .expect("default client must be valid")
+    }
+}
+
+impl Client for UreqClient {
+    fn get(&self, url: &str) -> Result<Response, Error> {
+        let mut request = self.agent.get(url);
+
+        for (name, value) in &self.headers {
+            request = request.set(name, value);
+        }
+
+        match request.call() {
+            Ok(response) => Ok(convert_response(response)),
+            Err(ureq::Error::Status(code, response)) => Err(Error::Status {
+                code,
+                retry_after: response.header("Retry-After").and_then(parse_retry_after),
+            }),
+            Err(ureq::Error::Transport(e)) => Err(convert_transport_error(e)),
+        }
+    }
+}
+
+// Connection failures and timeouts are separated from other transport errors, since only they
+// may succeed when the request is made again.
+fn convert_transport_error(e: ureq::Transport) -> Error {
+    let is_connection_error = match e.kind() {
+        ureq::ErrorKind::Dns
+        | ureq::ErrorKind::ConnectionFailed
+        | ureq::ErrorKind::ProxyConnect => true,
+        ureq::ErrorKind::Io => e
+            .source()
+            .and_then(|source| source.downcast_ref::<io::Error>())
+            .map(|source| is_connection_io_error(source.kind()))
+            .unwrap_or(false),
+        _ => false,
+    };
+
+    if is_connection_error {
+        Error::Connection(e.to_string())
+    } else {
+        Error::Transport(e.to_string())
+    }
+}
+
+fn is_connection_io_error(kind: io::ErrorKind) -> bool {
+    matches!(
+        kind,
+        io::ErrorKind::TimedOut
+            | io::ErrorKind::WouldBlock
+            | io::ErrorKind::ConnectionRefused
+            | io::ErrorKind::ConnectionReset
+            | io::ErrorKind::ConnectionAborted
+            | io::ErrorKind::BrokenPipe
+            | io::ErrorKind::UnexpectedEof
+    )
+}
+
+fn convert_response(response: ureq::Response) -> Response {
+    let status = response.status();
+
+    let headers = response
+        .headers_names()
+        .into_iter()
+        .filter_map(|name| {
+            response
+                .header(&name)
+                .map(|value| (name.clone(), value.to_string()))
+        })
+        .collect();
+
+    Response::new(status, headers, response.into_reader())
+}
+
+#[derive(Default)]
+pub struct Builder {
+    timeout: Option<Duration>,
+    connect_timeout: Option<Duration>,
+    proxy: Option<String>,
+    headers: Vec<(String, String)>,
+}
+
+impl Builder {
+    /// Sets the overall timeout of a request, including connecting and reading the body.
+    pub fn set_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Sets the timeout of establishing a connection.
+    pub fn set_connect_timeout(mut self, timeout: Duration) -> Self {
+        self.connect_timeout = Some(timeout);
+        self
+    }
+
+    /// Sets the proxy to use, e.g., `user:password@localhost:8080`.
+    pub fn set_proxy(mut self, proxy: &str) -> Self {
+        self.proxy = Some(proxy.to_owned());
+        self
+    }
+
+    /// Adds a header to be sent with every request.
+    pub fn add_header(mut self, name: &str, value: &str) -> Self {
+        self.headers.push((name.to_owned(), value.to_owned()));
+        self
+    }
+
+    pub fn build(self) -> Result<UreqClient, Error> {
+        let mut builder = ureq::AgentBuilder::new();
+
+        if let Some(timeout) = self.timeout {
+            builder = builder.timeout(timeout);
+        }
+
+        if let Some(timeout) = self.connect_timeout {
+            builder = builder.timeout_connect(timeout);
+        }
+
+        if let Some(proxy) = self.proxy {
+            let proxy = ureq::Proxy::new(&proxy).map_err(|e| Error::Transport(e.to_string()))?;
+            builder = builder.proxy(proxy);
+        }
+
+        let mut headers = vec![(String::from("User-Agent"), String::from(USER_AGENT))];
+        headers.extend(self.headers);
+
+        Ok(UreqClient {
+            agent: builder.build(),
+            headers,
+        })
+    }
+}
@@ -2,6 +2,7 @@ use std::collections::HashSet;
 
 use once_cell::sync::Lazy;
 use regex::{Captures, Regex};
+use serde::{Deserialize, Serialize};
 use unidecode::unidecode;
 
 static MINOR_WORDS: Lazy<HashSet<&'static str>> = Lazy::new(|| {
@@ -16,6 +17,70 @@ static MINOR_WORDS: Lazy<HashSet<&'static str>> = Lazy::new(|| {
 static ACRONYMS: Lazy<HashSet<&'static str>> =
     Lazy::new(|| vec!["dj", "ost"].into_iter().collect());
 
+/// A title split into its base and trailing annotations.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Title {
+    pub base: String,
+    pub featured_artists: Vec<String>,
+    pub performers: Vec<String>,
+    pub versions: Vec<Version>,
+}
+
+impl Title {
+    pub fn is_instrumental(&self) -> bool {
+        self.versions
+            .iter()
+            .any(|v| v.kind == VersionKind::Instrumental)
+    }
+
+    pub fn is_variant(&self) -> bool {
+        !self.versions.is_empty()
+    }
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum VersionKind {
+    Instrumental,
+    Acoustic,
+    Remix,
+    Alternate,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct Version {
+    pub kind: VersionKind,
+    pub tag: String,
+}
+
+impl Version {
+    fn parse(s: &str) -> Option<Version> {
+        static INSTRUMENTAL_RE: Lazy<Regex> =
+            Lazy::new(|| Regex::new(r"(?i)\binst(\.|rumental\b|$)").unwrap());
+        static REMIX_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?i)\bremix\b").unwrap());
+        static ACOUSTIC_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?i)\bacoustic\b").unwrap());
+        static ALTERNATE_RE: Lazy<Regex> =
+            Lazy::new(|| Regex::new(r"(?i)\bver(\.|sion\b|$)").unwrap());
+
+        let kind = if INSTRUMENTAL_RE.is_match(s) {
+            VersionKind::Instrumental
+        } else if REMIX_RE.is_match(s) {
+            VersionKind::Remix
+        } else if ACOUSTIC_RE.is_match(s) {
+            VersionKind::Acoustic
+        } else if ALTERNATE_RE.is_match(s) {
+            VersionKind::Alternate
+        } else {
+            return None;
+        };
+
+        Some(Version {
+            kind,
+            tag: s.into(),
+        })
+    }
+}
+
 pub fn capitalize(s: &str) -> String {
     let mut chs = s.chars();
 
@@ -56,6 +121,94 @@ pub fn titleize(s: &str) -> String {
     s.to_string()
 }
 
+/// Parses a title into its base and trailing parenthesized annotations.
+///
+/// Annotations are either featured artists (e.g., "(Feat. Kim Lip)"), version tags (e.g.,
+/// "(Inst.)", "(Chinese Ver.)", "(Remix)", "(Acoustic)"), or performers (e.g., "(Chuu, Go
+/// Won)"). A group is only considered a list of performers if it has more than one name or
+/// follows another annotation. Otherwise, it is kept as part of the base, since it is
+/// indistinguishable from a subtitle, e.g., "Neoui Ondo (Remind of You)".
+pub fn parse_title(s: &str) -> Title {
+    static FEATURING_RE: Lazy<Regex> =
+        Lazy::new(|| Regex::new(r"(?i)^(feat\.?|ft\.|featuring)\s+(.+)$").unwrap());
+
+    let mut rest = s.trim();
+    let mut groups = Vec::new();
+
+    while let Some((head, group)) = split_trailing_group(rest) {
+        if head.is_empty() {
+            break;
+        }
+
+        groups.push(group);
+        rest = head;
+    }
+
+    let mut title = Title {
+        base: rest.into(),
+        ..Title::default()
+    };
+
+    let mut is_annotated = false;
+
+    for group in groups.into_iter().rev() {
+        if let Some(caps) = FEATURING_RE.captures(group) {
+            title.featured_artists = split_names(&caps[2]);
+        } else if let Some(version) = Version::parse(group) {
+            title.versions.push(version);
+        } else if is_annotated || group.contains(',') {
+            title.performers = split_names(group);
+        } else {
+            title.base.push_str(&format!(" ({})", group));
+            continue;
+        }
+
+        is_annotated = true;
+    }
+
+    title
+}
+
+/// Splits the last parenthesized (or bracketed) group from the end of a string.
+///
+/// This returns the trimmed text before the group and the trimmed text inside the group. Nested
+/// parentheses, e.g., "(Feat. Amber of f(x))", are kept in the group.
+pub fn split_trailing_group(s: &str) -> Option<(&str, &str)> {
+    let s = s.trim_end();
+
+    let (open, close) = match s.chars().last() {
+        Some(')') => ('(', ')'),
+        Some(']') => ('[', ']'),
+        _ => return None,
+    };
+
+    let mut depth = 0;
+
+    for (i, c) in s.char_indices().rev() {
+        if c == close {
+            depth += 1;
+        } else if c == open {
+            depth -= 1;
+
+            if depth == 0 {
+                let head = s[..i].trim_end();
+                let group = s[i + 1..s.len() - 1].trim();
+                return Some((head, group));
+            }
+        }
+    }
+
+    None
+}
+
+fn split_names(s: &str) -> Vec<String> {
+    s.split(',')
+        .map(|name| name.trim())
+        .filter(|name| !name.is_empty())
+        .map(String::from)
+        .collect()
+}
+
 // This works similarly to `ActiveSupport::Inflector.parameterize`.
 pub fn parameterize(s: &str) -> String {
     static RE1: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?i)[^a-z0-9-_]+").unwrap());
@@ -124,6 +277,84 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_parse_title() {
+        let title = parse_title("See Saw (Feat. Kim Lip) (Chuu, Go Won)");
+        assert_eq!(title.base, "See Saw");
+        assert_eq!(title.featured_artists, vec!["Kim Lip"]);
+        assert_eq!(title.performers, vec!["Chuu", "Go Won"]);
+        assert!(title.versions.is_empty());
+
+        let title = parse_title("Kkumkkuneun Maeumeuro (Chinese Ver.)");
+        assert_eq!(title.base, "Kkumkkuneun Maeumeuro");
+        assert_eq!(
+            title.versions,
+            vec![Version {
+                kind: VersionKind::Alternate,
+                tag: String::from("Chinese Ver."),
+            }]
+        );
+        assert!(title.is_variant());
+        assert!(!title.is_instrumental());
+
+        let title = parse_title("비밀이야 (Inst.)");
+        assert_eq!(title.base, "비밀이야");
+        assert!(title.is_instrumental());
+
+        let title = parse_title("モーニングコーヒー(20th Anniversary Ver.)");
+        assert_eq!(title.base, "モーニングコーヒー");
+        assert_eq!(title.versions[0].kind, VersionKind::Alternate);
+
+        let title = parse_title("Love Don't Hurt (Feat. Amber of f(x))");
+        assert_eq!(title.base, "Love Don't Hurt");
+        assert_eq!(title.featured_artists, vec!["Amber of f(x)"]);
+
+        let title = parse_title("Straight Up (Feat. DJ Wegun, kim hyo eun)");
+        assert_eq!(title.featured_artists, vec!["DJ Wegun", "kim hyo eun"]);
+
+        let title = parse_title("Girl's Talk (이브, 츄)");
+        assert_eq!(title.base, "Girl's Talk");
+        assert_eq!(title.performers, vec!["이브", "츄"]);
+
+        let title = parse_title("Heart Attack (츄)");
+        assert_eq!(title.base, "Heart Attack (츄)");
+        assert!(title.performers.is_empty());
+
+        let title = parse_title("neoui ondo (Remind of You)");
+        assert_eq!(title.base, "neoui ondo (Remind of You)");
+
+        let title = parse_title("Butterfly (Acoustic) (Kim Lip)");
+        assert_eq!(title.base, "Butterfly");
+        assert_eq!(title.versions[0].kind, VersionKind::Acoustic);
+        assert_eq!(title.performers, vec!["Kim Lip"]);
+
+        let title = parse_title("Hi High [DJ Mix Remix]");
+        assert_eq!(title.base, "Hi High");
+        assert_eq!(title.versions[0].kind, VersionKind::Remix);
+
+        let title = parse_title("(츄)");
+        assert_eq!(title.base, "(츄)");
+    }
+
+    #[test]
+    fn test_split_trailing_group() {
+        assert_eq!(
+            split_trailing_group("Heart Attack (츄)"),
+            Some(("Heart Attack", "츄"))
+        );
+        assert_eq!(
+            split_trailing_group("Love Don't Hurt (Feat. Amber of f(x))"),
+            Some(("Love Don't Hurt", "Feat. Amber of f(x)"))
+        );
+        assert_eq!(
+            split_trailing_group("Hi High [Remix]"),
+            Some(("Hi High", "Remix"))
+        );
+        assert_eq!(split_trailing_group("(츄)"), Some(("", "츄")));
+        assert_eq!(split_trailing_group("La La La"), None);
+        assert_eq!(split_trailing_group("f(x))"), None);
+    }
+
     #[test]
     fn test_parameterize() {
         assert_eq!(
@@ -0,0 +1,152 @@
+use std::str::FromStr;
+
+use crate::util::inflector::split_trailing_group;
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Script {
+    Latin,
+    Hangul,
+    Kana,
+    Han,
+}
+
+impl Script {
+    pub fn from_char(c: char) -> Option<Script> {
+        match c as u32 {
+            0x0041..=0x005a
+            | 0x0061..=0x007a
+            | 0x00c0..=0x024f
+            | 0xff21..=0xff3a
+            | 0xff41..=0xff5a => Some(Script::Latin),
+            0x1100..=0x11ff
+            | 0x3130..=0x318f
+            | 0xa960..=0xa97f
+            | 0xac00..=0xd7af
+            | 0xd7b0..=0xd7ff => Some(Script::Hangul),
+            0x3040..=0x309f | 0x30a0..=0x30ff | 0x31f0..=0x31ff | 0xff66..=0xff9d => {
+                Some(Script::Kana)
+            }
+            0x3400..=0x4dbf | 0x4e00..=0x9fff | 0xf900..=0xfaff | 0x20000..=0x2fa1f => {
+                Some(Script::Han)
+            }
+            _ => None,
+        }
+    }
+}
+
+impl FromStr for Script {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "latin" => Ok(Script::Latin),
+            "hangul" => Ok(Script::Hangul),
+            "kana" => Ok(Script::Kana),
+            "han" => Ok(Script::Han),
+            _ => Err(format!("invalid script: {}", s)),
+        }
+    }
+}
+
+/// Returns the script that determines the locale of a text.
+///
+/// Any kana takes precedence, e.g., a Japanese text mixing kanji and kana is in kana, followed by
+/// hangul, han, and latin. A text without letters is not in any script.
+pub fn primary_script(s: &str) -> Option<Script> {
+    let scripts: Vec<Script> = s.chars().filter_map(Script::from_char).collect();
+
+    [Script::Kana, Script::Hangul, Script::Han, Script::Latin]
+        .iter()
+        .find(|script| scripts.contains(script))
+        .copied()
+}
+
+/// Guesses the locale of a title from the scripts of its characters.
+///
+/// Trailing parenthesized annotations, e.g., performers or version tags, are ignored unless they
+/// are the only text in the title. Han characters without any kana or hangul are ambiguous, so
+/// they resolve to `han_locale`.
+///
+/// The result is one of `en`, `ko`, `ja`, the given `han_locale`, or `und` when no letters are
+/// found.
+pub fn detect_locale<'a>(s: &str, han_locale: &'a str) -> &'a str {
+    let base = strip_annotations(s);
+    let text = if base.is_empty() { s } else { base };
+
+    match primary_script(text) {
+        Some(Script::Kana) => "ja",
+        Some(Script::Hangul) => "ko",
+        Some(Script::Han) => han_locale,
+        Some(Script::Latin) => "en",
+        None => "und",
+    }
+}
+
+fn strip_annotations(s: &str) -> &str {
+    let mut s = s.trim_end();
+
+    while let Some((head, _)) = split_trailing_group(s) {
+        s = head;
+    }
+
+    s
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_char() {
+        assert_eq!(Script::from_char('a'), Some(Script::Latin));
+        assert_eq!(Script::from_char('É'), Some(Script::Latin));
+        assert_eq!(Script::from_char('츄'), Some(Script::Hangul));
+        assert_eq!(Script::from_char('の'), Some(Script::Kana));
+        assert_eq!(Script::from_char('ー'), Some(Script::Kana));
+        assert_eq!(Script::from_char('種'), Some(Script::Han));
+        assert_eq!(Script::from_char('1'), None);
+        assert_eq!(Script::from_char(' '), None);
+    }
+
+    #[test]
+    fn test_from_str() {
+        assert_eq!("latin".parse(), Ok(Script::Latin));
+        assert_eq!("hangul".parse(), Ok(Script::Hangul));
+        assert_eq!("kana".parse(), Ok(Script::Kana));
+        assert_eq!("han".parse(), Ok(Script::Han));
+
+        assert_eq!(
+            "cyrillic".parse::<Script>(),
+            Err(String::from("invalid script: cyrillic"))
+        );
+    }
+
+    #[test]
+    fn test_primary_script() {
+        assert_eq!(primary_script("Heart Attack"), Some(Script::Latin));
+        assert_eq!(primary_script("Heart Attack (츄)"), Some(Script::Hangul));
+        assert_eq!(primary_script("恋愛レボリューション21"), Some(Script::Kana));
+        assert_eq!(primary_script("恋愛"), Some(Script::Han));
+        assert_eq!(primary_script("1/6"), None);
+    }
+
+    #[test]
+    fn test_detect_locale() {
+        assert_eq!(detect_locale("비밀이야", "zh"), "ko");
+        assert_eq!(detect_locale("Heart Attack (츄)", "zh"), "en");
+        assert_eq!(detect_locale("Girl's Talk (이브, 츄)", "zh"), "en");
+        assert_eq!(detect_locale("꿈꾸는 마음으로 (Chinese Ver.)", "zh"), "ko");
+        assert_eq!(detect_locale("서핑해 (Surfin')", "zh"), "ko");
+        assert_eq!(
+            detect_locale("Love Don't Hurt (Feat. Amber of f(x))", "zh"),
+            "en"
+        );
+        assert_eq!(detect_locale("プラットホームシンドローム", "ja"), "ja");
+        assert_eq!(detect_locale("愛の種(20th Anniversary Ver.)", "ja"), "ja");
+        assert_eq!(detect_locale("二十歳", "ja"), "ja");
+        assert_eq!(detect_locale("梦想", "zh"), "zh");
+        assert_eq!(detect_locale("(츄)", "zh"), "ko");
+        assert_eq!(detect_locale(":(", "zh"), "und");
+        assert_eq!(detect_locale("1/6", "zh"), "und");
+    }
+}
@@ -1,5 +1,5 @@
 use std::{
-    fs::{self, File},
+    fs::{self, File, OpenOptions},
     io::{self, Write},
     path::{Path, PathBuf},
 };
@@ -35,6 +35,7 @@ impl Writer {
         artist_id: &str,
         album: &Album,
     ) -> io::Result<()> {
+        self.check_songs(artist_id, album)?;
         self.write_album(renderer, artist_id, album)?;
         self.write_songs(renderer, artist_id, album)?;
         self.write_tracklist(renderer, artist_id, album)?;
@@ -54,23 +55,61 @@ impl Writer {
         write_file(&dst, result.as_bytes())
     }
 
+    // Checks that no new song would overwrite an existing song file before anything is written.
+    fn check_songs(&self, artist_id: &str, album: &Album) -> io::Result<()> {
+        let dst_prefix = self.dst_prefix.join("songs").join(artist_id);
+
+        for song in album.songs.iter().filter(|s| !s.is_existing) {
+            let dst = dst_prefix.join(format!("{}.toml", song.id()));
+
+            if dst.exists() {
+                return Err(song_exists_error(&dst));
+            }
+        }
+
+        Ok(())
+    }
+
     fn write_songs(&self, renderer: &Renderer, artist_id: &str, album: &Album) -> io::Result<()> {
         let dst_prefix = self.dst_prefix.join("songs").join(artist_id);
 
         fs::create_dir_all(&dst_prefix)?;
 
-        for song in &album.songs {
+        // Existing songs are only referenced by the tracklist.
+        for song in album.songs.iter().filter(|s| !s.is_existing) {
             let basename = song.id();
             let dst = dst_prefix.join(format!("{}.toml", basename));
 
-            let result = renderer.render_song(song);
+            let result = renderer.render_song(artist_id, song);
+
+            write_new_file(&dst, result.as_bytes()).map_err(|e| match e.kind() {
+                io::ErrorKind::AlreadyExists => song_exists_error(&dst),
+                _ => e,
+            })?;
 
-            write_file(&dst, result.as_bytes())?;
+            if let Some(ref lyrics) = song.lyrics {
+                self.write_lyrics(artist_id, song.id(), &lyrics.text)?;
+            }
         }
 
         Ok(())
     }
 
+    fn write_lyrics(&self, artist_id: &str, song_id: &str, text: &str) -> io::Result<()> {
+        let dst_prefix = self
+            .dst_prefix
+            .join("-attachments")
+            .join("songs")
+            .join(artist_id)
+            .join(song_id);
+
+        fs::create_dir_all(&dst_prefix)?;
+
+        let dst = dst_prefix.join("lyrics.txt");
+
+        write_file(&dst, format!("{}\n", text).as_bytes())
+    }
+
     fn write_tracklist(
         &self,
         renderer: &Renderer,
@@ -93,7 +132,12 @@ impl Writer {
         write_file(&dst, result.as_bytes())
     }
 
-    pub fn write_artwork(&self, artist_id: &str, album: &Album) -> io::Result<()> {
+    pub fn write_artwork(
+        &self,
+        downloader: &Downloader,
+        artist_id: &str,
+        album: &Album,
+    ) -> io::Result<()> {
         let mut dst_prefix = self
             .dst_prefix
             .join("-attachments")
@@ -110,16 +154,11 @@ impl Writer {
         let final_dst = dst_prefix.join("default.jpg");
 
         if let Some(ref artwork_url) = album.artwork_url {
-            let downloader = Downloader::new();
-
             downloader
                 .save(artwork_url, &original_dst)
                 .map_err(|e| match e {
                     http::Error::Io(inner) => inner,
-                    http::Error::RequestFailed => {
-                        io::Error::new(io::ErrorKind::Other, "request failed")
-                    }
-                    http::Error::EmptyBody => io::Error::new(io::ErrorKind::Other, "empty body"),
+                    _ => io::Error::new(io::ErrorKind::Other, e),
                 })?;
 
             optimize(&original_dst, &final_dst)?;
@@ -137,6 +176,29 @@ where
     file.write_all(data)
 }
 
+// Writes a file that must not exist yet.
+fn write_new_file<P>(pathname: P, data: &[u8]) -> io::Result<()>
+where
+    P: AsRef<Path>,
+{
+    let mut file = OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(pathname)?;
+
+    file.write_all(data)
+}
+
+fn song_exists_error(path: &Path) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::AlreadyExists,
+        format!(
+            "song already exists: {} (link it or change its ID)",
+            path.display()
+        ),
+    )
+}
+
 fn optimize<P, Q>(src: P, dst: Q) -> io::Result<()>
 where
     P: AsRef<Path>,
@@ -158,3 +220,58 @@ where
         None => Err(io::Error::new(io::ErrorKind::Other, "invalid versions")),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        models::{album, song, Name},
+        util::tmp_dir,
+    };
+
+    use super::*;
+
+    fn build_album(is_existing: bool) -> Album {
+        let mut song = song::Builder::new()
+            .set_id("uju-reul-jul-ge")
+            .set_position(1)
+            .set_duration(274)
+            .add_name(Name::new("우주를 줄게", "ko", true, true))
+            .build();
+
+        song.is_existing = is_existing;
+
+        album::Builder::new()
+            .set_id("red-planet")
+            .set_kind(album::Kind::Lp)
+            .set_country("KR")
+            .set_released_on("2016-03-29")
+            .set_url("https://lp.dev/albums/1")
+            .add_name(Name::new("Red Planet", "en", true, true))
+            .add_song(song)
+            .build()
+    }
+
+    #[test]
+    fn test_write_templates() -> io::Result<()> {
+        let dir = tmp_dir("writer");
+        let song_dst = dir.join("songs/bol4/uju-reul-jul-ge.toml");
+
+        fs::create_dir_all(song_dst.parent().unwrap())?;
+        fs::write(&song_dst, "# existing")?;
+
+        let writer = Writer::new(&dir);
+        let renderer = Renderer::new();
+
+        let e = writer
+            .write_templates(&renderer, "bol4", &build_album(false))
+            .unwrap_err();
+        assert_eq!(e.kind(), io::ErrorKind::AlreadyExists);
+        assert!(!dir.join("albums/bol4/red-planet.toml").exists());
+
+        writer.write_templates(&renderer, "bol4", &build_album(true))?;
+        assert!(dir.join("albums/bol4/red-planet.toml").exists());
+        assert_eq!(fs::read_to_string(&song_dst)?, "# existing");
+
+        fs::remove_dir_all(&dir)
+    }
+}